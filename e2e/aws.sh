@@ -6,6 +6,9 @@ combinations=(
 	"service=s3:200"
 	"region=eu-central-1:200"
 	"network_border_group=ap-southeast-1:200"
+	"region=us-east-1&ipv6=true:200"
+	"service=s3&ipv4=true&ipv6=true:200"
+	"ipv4=false&ipv6=false:400"
 	":200"
 )
 
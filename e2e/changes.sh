@@ -0,0 +1,23 @@
+#!/bin/bash
+
+combinations=(
+	":200"
+	"provider=aws:200"
+	"provider=aws&since=2020-01-01T00%3A00%3A00Z:200"
+	"since=2020-01-01T00%3A00%3A00Z:200"
+	"provider=aws&since=not-a-token:404"
+	"since=not-a-token:400"
+	"provider=unknown:400"
+)
+
+for combo in "${combinations[@]}"; do
+	params="${combo%:*}"
+	expected="${combo##*:}"
+	status=$(curl -s -o /dev/null -w "%{http_code}" "http://localhost:8000/v1/changes?$params")
+	if [ "$status" -ne "$expected" ]; then
+		echo "Changes test failed for params: $params. Expected $expected, got $status"
+		exit 1
+	else
+		echo "Changes test passed for params: $params. Expected and got $status"
+	fi
+done
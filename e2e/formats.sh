@@ -0,0 +1,39 @@
+#!/bin/bash
+
+combinations=(
+	"/v1/aws?ipv4=true&format=text||text/plain"
+	"/v1/aws?ipv4=true&format=csv||text/csv"
+	"/v1/aws?ipv4=true&format=ndjson||application/x-ndjson"
+	"/v1/aws?ipv4=true|text/plain|text/plain"
+	"/v1/ranges?provider=cloudflare|text/csv|text/csv"
+	"/v1/lookup?ip=104.16.0.1|application/x-ndjson|application/x-ndjson"
+	"/v1/ranges?provider=cloudflare|application/json|application/json"
+	"/v1/ranges?provider=cloudflare||application/json"
+)
+
+for combo in "${combinations[@]}"; do
+	IFS="|" read -r path accept expected <<< "$combo"
+	content_type=$(curl -s -o /dev/null -H "Accept: ${accept:-*/*}" -w "%{content_type}" "http://localhost:8000$path")
+	if [[ "$content_type" != "$expected"* ]]; then
+		echo "Format test failed for path: $path with Accept: $accept. Expected $expected, got $content_type"
+		exit 1
+	else
+		echo "Format test passed for path: $path with Accept: $accept. Expected and got $expected"
+	fi
+done
+
+status=$(curl -s -o /dev/null -w "%{http_code}" "http://localhost:8000/v1/aws?ipv4=true&format=xml")
+if [ "$status" -ne 400 ]; then
+	echo "Format test failed for unknown format. Expected 400, got $status"
+	exit 1
+else
+	echo "Format test passed for unknown format. Expected and got $status"
+fi
+
+# NDJSON is streamed rather than sent as one buffered body
+if curl -s -D - -o /dev/null "http://localhost:8000/v1/ranges?provider=cloudflare&format=ndjson" | grep -qi "^transfer-encoding: chunked"; then
+	echo "Format test passed for streamed NDJSON"
+else
+	echo "Format test failed for streamed NDJSON. Expected a chunked response"
+	exit 1
+fi
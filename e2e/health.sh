@@ -0,0 +1,18 @@
+#!/bin/bash
+
+combinations=(
+	"/health:200"
+	"/health/live:200"
+	"/health/ready:200"
+)
+
+for combo in "${combinations[@]}"; do
+	IFS=":" read -r path expected <<< "$combo"
+	status=$(curl -s -o /dev/null -w "%{http_code}" "http://localhost:8000$path")
+	if [ "$status" -ne "$expected" ]; then
+		echo "Health test failed for path: $path. Expected $expected, got $status"
+		exit 1
+	else
+		echo "Health test passed for path: $path. Expected and got $status"
+	fi
+done
@@ -0,0 +1,25 @@
+#!/bin/bash
+
+combinations=(
+	"ip=104.16.0.1:200"
+	"ip=2606:4700::1:200"
+	"ip=3.5.140.1:200"
+	"ip=104.16.0.0/16&provider=cloudflare:200"
+	"ip=104.16.0.1&longest=true:200"
+	"ip=104.16.0.1&provider=aws:404"
+	"ip=104.16.0.1&provider=nope:400"
+	"ip=not-an-ip:400"
+	":400"
+)
+
+for combo in "${combinations[@]}"; do
+	params="${combo%:*}"
+	expected="${combo##*:}"
+	status=$(curl -s -o /dev/null -w "%{http_code}" "http://localhost:8000/v1/lookup?$params")
+	if [ "$status" -ne "$expected" ]; then
+		echo "Lookup test failed for params: $params. Expected $expected, got $status"
+		exit 1
+	else
+		echo "Lookup test passed for params: $params. Expected and got $status"
+	fi
+done
@@ -0,0 +1,22 @@
+#!/bin/bash
+
+combinations=(
+	"provider=aws,gcp,cloudflare&family=ipv4:200"
+	"provider=aws&region=us-east-1&family=ipv6:200"
+	"provider=linode,digitalocean&country=us:200"
+	"family=ipv4,ipv6:200"
+	"provider=unknown:400"
+	"family=ipv5:400"
+	":200"
+)
+
+for combo in "${combinations[@]}"; do
+	IFS=":" read -r params expected <<< "$combo"
+	status=$(curl -s -o /dev/null -w "%{http_code}" "http://localhost:8000/v1/ranges?$params")
+	if [ "$status" -ne "$expected" ]; then
+		echo "Ranges test failed for params: $params. Expected $expected, got $status"
+		exit 1
+	else
+		echo "Ranges test passed for params: $params. Expected and got $status"
+	fi
+done
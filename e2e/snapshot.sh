@@ -0,0 +1,23 @@
+#!/bin/bash
+
+combinations=(
+	"aws"
+	"azure"
+	"cloudflare"
+	"digitalocean"
+	"fastly"
+	"gcp"
+	"linode"
+	"oracle"
+)
+
+for provider in "${combinations[@]}"; do
+	file="snapshots/$provider.json"
+	count=$(jq '.prefixes | length' "$file" 2>/dev/null)
+	if [ -z "$count" ] || [ "$count" -eq 0 ]; then
+		echo "Snapshot test failed for provider: $provider. Expected prefixes in $file"
+		exit 1
+	else
+		echo "Snapshot test passed for provider: $provider. Found $count prefixes in $file"
+	fi
+done
@@ -0,0 +1,16 @@
+#!/bin/bash
+
+status=$(curl -s -o /tmp/status.json -w "%{http_code}" "http://localhost:8000/v1/status")
+if [ "$status" -ne 200 ]; then
+	echo "Status test failed. Expected 200, got $status"
+	exit 1
+fi
+
+for field in '"provider":"aws"' '"last_success":"' '"sync_token":"' '"data_age_seconds":'; do
+	if ! grep -q "$field" /tmp/status.json; then
+		echo "Status test failed. Response is missing $field"
+		exit 1
+	fi
+done
+
+echo "Status test passed. Expected and got 200 with provider freshness"
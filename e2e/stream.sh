@@ -0,0 +1,22 @@
+#!/bin/bash
+
+combinations=(
+	":200"
+	"provider=aws&deltas=true:200"
+	"region=us-east-1&last_event_id=1:200"
+	"family=ipv5:400"
+	"provider=unknown:400"
+)
+
+for combo in "${combinations[@]}"; do
+	params="${combo%:*}"
+	expected="${combo##*:}"
+	# The stream stays open, so only wait for the response headers
+	status=$(curl -s -o /dev/null -m 2 -w "%{http_code}" "http://localhost:8000/v1/stream?$params")
+	if [ "$status" -ne "$expected" ]; then
+		echo "Stream test failed for params: $params. Expected $expected, got $status"
+		exit 1
+	else
+		echo "Stream test passed for params: $params. Expected and got $status"
+	fi
+done
@@ -1,7 +1,7 @@
 #!/bin/bash
 
 # Define the health check URL
-HEALTH_URL="http://localhost:8000/health"
+HEALTH_URL="http://localhost:8000/health/ready"
 # Maximum number of attempts
 MAX_ATTEMPTS=30
 # Delay between attempts in seconds
@@ -9,7 +9,7 @@ SLEEP_DURATION=2
 # Counter for attempts
 ATTEMPT=0
 
-# Loop until the health check returns "ok" or the max attempts are reached
+# Loop until the readiness check returns "ok" or the max attempts are reached
 until [ $ATTEMPT -ge $MAX_ATTEMPTS ]; do
   RESPONSE=$(curl -s $HEALTH_URL)
   STATUS=$(echo $RESPONSE | jq -r '.status')
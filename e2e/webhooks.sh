@@ -0,0 +1,16 @@
+#!/bin/bash
+
+combinations=(
+	"/v1/webhooks/deliveries:200"
+)
+
+for combo in "${combinations[@]}"; do
+	IFS=":" read -r path expected <<< "$combo"
+	status=$(curl -s -o /dev/null -w "%{http_code}" "http://localhost:8000$path")
+	if [ "$status" -ne "$expected" ]; then
+		echo "Webhooks test failed for path: $path. Expected $expected, got $status"
+		exit 1
+	else
+		echo "Webhooks test passed for path: $path. Expected and got $status"
+	fi
+done
@@ -1,128 +1,239 @@
-use crate::fetchers::{update_all, IntegrationResult};
+use crate::config;
+use crate::fetchers::error::FetchError;
+use crate::fetchers::http::HttpClient;
+use crate::fetchers::normalized::NormalizedPrefix;
+use crate::fetchers::{
+    find, refresh_policy, registry, update_integration, Integration, UpstreamVersion,
+};
+use crate::history::{self, Generation};
+use crate::index::PrefixIndex;
+use crate::snapshot;
+use crate::stream;
+use crate::webhooks;
+use chrono::{DateTime, Utc};
 use dashmap::DashMap;
 use lazy_static::lazy_static;
-use rocket::tokio::time::{self, Duration};
+use rocket::tokio::time;
 use std::sync::Arc;
 use tokio::task;
-use tracing::info;
+use tracing::{info, warn};
 use uuid::Uuid;
 
-#[allow(dead_code)]
-pub struct IntegrationCache<T> {
-    pub data: Option<T>,
+pub struct IntegrationCache {
+    pub index: PrefixIndex,
+    pub rejected: usize,
+    pub version: Option<UpstreamVersion>,
+    pub fetched_at: DateTime<Utc>,
+    // Loaded from a snapshot at startup rather than fetched by this process
+    pub restored: bool,
 }
 
-impl<T> IntegrationCache<T> {
-    pub fn new(data: Option<T>) -> Self {
-        IntegrationCache { data }
+impl IntegrationCache {
+    pub fn new(
+        prefixes: Vec<NormalizedPrefix>,
+        rejected: usize,
+        version: Option<UpstreamVersion>,
+    ) -> Self {
+        IntegrationCache {
+            index: PrefixIndex::build(prefixes.into_iter().map(Arc::new)),
+            rejected,
+            version,
+            fetched_at: Utc::now(),
+            restored: false,
+        }
+    }
+
+    pub fn restore(
+        prefixes: Vec<NormalizedPrefix>,
+        rejected: usize,
+        version: Option<UpstreamVersion>,
+        fetched_at: DateTime<Utc>,
+    ) -> Self {
+        IntegrationCache {
+            fetched_at,
+            restored: true,
+            ..IntegrationCache::new(prefixes, rejected, version)
+        }
     }
+
+    pub fn prefixes(&self) -> &[Arc<NormalizedPrefix>] {
+        self.index.records()
+    }
+}
+
+// Outcome of the fetches of an integration, kept even when they fail
+#[derive(Debug, Clone)]
+pub struct FetchAttempt {
+    pub at: DateTime<Utc>,
+    pub error: Option<FetchError>,
+    // Last fetch that succeeded, including ones that found the data unchanged
+    pub last_success: Option<DateTime<Utc>>,
 }
 
 lazy_static! {
     // Define the global cache as a map of integration names to their data
-    pub static ref CACHE: Arc<DashMap<String, Box<dyn std::any::Any + Send + Sync>>> = Arc::new(DashMap::new());
-}
+    pub static ref CACHE: Arc<DashMap<String, Arc<IntegrationCache>>> = Arc::new(DashMap::new());
 
-pub async fn initialize_cache() {
-    info!("Initializing cache");
+    // Last fetch attempt of every integration, successful or not
+    pub static ref ATTEMPTS: DashMap<String, FetchAttempt> = DashMap::new();
+}
 
-    // Initialize the cache synchronously
-    update_cache().await;
+pub fn get(integration_name: &str) -> Option<Arc<IntegrationCache>> {
+    CACHE
+        .get(integration_name)
+        .map(|entry| entry.value().clone())
+}
 
-    // Start periodic updates asynchronously
-    task::spawn(async {
-        periodic_update_cache().await;
-    });
+pub fn record_attempt(integration_name: &str, error: Option<FetchError>) {
+    let now = Utc::now();
+    let last_success = match error {
+        None => Some(now),
+        Some(_) => last_attempt(integration_name).and_then(|attempt| attempt.last_success),
+    };
+    ATTEMPTS.insert(
+        integration_name.to_string(),
+        FetchAttempt {
+            at: now,
+            error,
+            last_success,
+        },
+    );
 }
 
-async fn update_cache() {
-    // Generate a unique execution ID for this cache update
-    let execution_id = Uuid::new_v4();
-    info!(execution_id = %execution_id, "Starting cache update");
+pub fn last_attempt(integration_name: &str) -> Option<FetchAttempt> {
+    ATTEMPTS
+        .get(integration_name)
+        .map(|entry| entry.value().clone())
+}
 
-    // Update data for all integrations
-    let data = update_all(execution_id).await;
+// Serve the snapshots written by a previous run until the first fetches complete
+pub fn restore_snapshots() {
+    if !config::get().snapshot.enabled {
+        return;
+    }
 
-    for (integration_name, integration_result) in data {
-        match integration_result {
-            IntegrationResult::Aws(aws_cache) => {
-                CACHE.insert(integration_name.clone(), Box::new(aws_cache));
-                info!(
-                    integration_name = integration_name.as_str(),
-                    execution_id = %execution_id,
-                    "Cache updated for AWS integration"
-                );
-            }
-            IntegrationResult::Azure(azure_cache) => {
-                CACHE.insert(integration_name.clone(), Box::new(azure_cache));
-                info!(
-                    integration_name = integration_name.as_str(),
-                    execution_id = %execution_id,
-                    "Cache updated for Azure integration"
-                );
-            }
-            IntegrationResult::Cloudflare(cloudflare_cache) => {
-                CACHE.insert(integration_name.clone(), Box::new(cloudflare_cache));
-                info!(
-                    integration_name = integration_name.as_str(),
-                    execution_id = %execution_id,
-                    "Cache updated for Cloudflare integration"
-                );
-            }
-            IntegrationResult::DigitalOcean(digital_ocean_cache) => {
-                CACHE.insert(integration_name.clone(), Box::new(digital_ocean_cache));
-                info!(
-                    integration_name = integration_name.as_str(),
-                    execution_id = %execution_id,
-                    "Cache updated for Digital Ocean integration"
-                );
-            }
-            IntegrationResult::Fastly(fastly_cache) => {
-                CACHE.insert(integration_name.clone(), Box::new(fastly_cache));
+    for integration in registry() {
+        match snapshot::load(integration.name()) {
+            Ok(Some(integration_cache)) => {
                 info!(
-                    integration_name = integration_name.as_str(),
-                    execution_id = %execution_id,
-                    "Cache updated for Fastly integration"
-                );
-            }
-            IntegrationResult::Gcp(gcp_cache) => {
-                CACHE.insert(integration_name.clone(), Box::new(gcp_cache));
-                info!(
-                    integration_name = integration_name.as_str(),
-                    execution_id = %execution_id,
-                    "Cache updated for GCP integration"
-                );
-            }
-            IntegrationResult::Linode(linode_cache) => {
-                CACHE.insert(integration_name.clone(), Box::new(linode_cache));
-                info!(
-                    integration_name = integration_name.as_str(),
-                    execution_id = %execution_id,
-                    "Cache updated for Linode integration"
-                );
-            }
-            IntegrationResult::Oracle(oracle_cache) => {
-                CACHE.insert(integration_name.clone(), Box::new(oracle_cache));
-                info!(
-                    integration_name = integration_name.as_str(),
-                    execution_id = %execution_id,
-                    "Cache updated for Oracle integration"
+                    integration_name = integration.name(),
+                    prefixes = integration_cache.prefixes().len(),
+                    fetched_at = %integration_cache.fetched_at,
+                    "Restored {} integration from snapshot", integration.display_name()
                 );
+                CACHE.insert(integration.name().to_string(), Arc::new(integration_cache));
             }
+            Ok(None) => {}
+            Err(err) => warn!(
+                integration_name = integration.name(),
+                "Ignoring {} snapshot: {}",
+                integration.display_name(),
+                err
+            ),
+        }
+    }
+}
+
+pub async fn initialize_cache(client: Arc<HttpClient>) {
+    info!("Initializing cache");
+
+    // Every integration runs on its own schedule, so a slow upstream does not hold back the others
+    for integration in registry() {
+        let client = client.clone();
+        task::spawn(async move {
+            periodic_update_cache(integration.as_ref(), client).await;
+        });
+    }
+}
+
+// Serve an update, returning its changes unless it is the first data of the integration
+async fn store(
+    integration_name: &str,
+    integration_cache: IntegrationCache,
+    execution_id: Uuid,
+) -> Option<Generation> {
+    let integration_cache = Arc::new(integration_cache);
+    let display_name =
+        find(integration_name).map_or(integration_name, |integration| integration.display_name());
+    info!(
+        integration_name = integration_name,
+        prefixes = integration_cache.prefixes().len(),
+        rejected = integration_cache.rejected,
+        execution_id = %execution_id,
+        "Cache updated for {} integration", display_name
+    );
+
+    // Serializing and writing a snapshot of several MB would stall the async worker
+    if config::get().snapshot.enabled {
+        let name = integration_name.to_string();
+        let snapshot_cache = integration_cache.clone();
+        let saved = task::spawn_blocking(move || snapshot::save(&name, &snapshot_cache))
+            .await
+            .unwrap_or_else(|err| Err(format!("Snapshot task failed: {}", err)));
+        if let Err(err) = saved {
+            warn!(
+                integration_name = integration_name,
+                execution_id = %execution_id,
+                "Failed to save {} snapshot: {}", display_name, err
+            );
         }
     }
 
-    info!(execution_id = %execution_id, "Cache update completed");
+    let generation = history::record(
+        integration_name,
+        get(integration_name).as_deref(),
+        &integration_cache,
+    );
+    CACHE.insert(integration_name.to_string(), integration_cache);
+    generation
 }
 
-async fn periodic_update_cache() {
-    info!("Starting periodic cache updates");
+async fn periodic_update_cache(integration: &dyn Integration, client: Arc<HttpClient>) {
+    let policy = refresh_policy(integration);
+    info!(
+        integration_name = integration.name(),
+        interval = policy.interval.as_secs(),
+        "Starting periodic cache updates"
+    );
 
-    // Start periodic updates
-    let mut interval = time::interval(Duration::from_secs(300));
+    // Fetch right away, then after each jittered interval, backing off while fetches fail
+    let mut failures = 0;
     loop {
-        interval.tick().await;
-        info!("Performing periodic cache update");
-        update_cache().await;
+        let execution_id = Uuid::new_v4();
+        info!(
+            integration_name = integration.name(),
+            execution_id = %execution_id,
+            "Performing periodic cache update"
+        );
+
+        let delay = match update_integration(integration, &client, execution_id).await {
+            Ok(updated) => {
+                if let Some(integration_cache) = updated {
+                    let generation =
+                        store(integration.name(), integration_cache, execution_id).await;
+
+                    // The first data of an integration is a baseline, and unchanged data is no change
+                    if let Some(generation) = generation {
+                        let generation_id = stream::publish(integration.name(), &generation);
+                        webhooks::notify(integration.name(), generation_id, &generation);
+                    }
+                }
+                failures = 0;
+                policy.next_refresh()
+            }
+            Err(_) => {
+                failures += 1;
+                let delay = policy.backoff(failures);
+                warn!(
+                    integration_name = integration.name(),
+                    execution_id = %execution_id,
+                    failures = failures,
+                    "Retrying {} integration in {}s", integration.display_name(), delay.as_secs()
+                );
+                delay
+            }
+        };
+
+        time::sleep(delay).await;
     }
 }
@@ -0,0 +1,318 @@
+use lazy_static::lazy_static;
+use rocket::figment::providers::{Env, Format, Serialized, Toml};
+use rocket::figment::Figment;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Config file read at startup, unless overridden through IP_RANGES_CONFIG
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+// Prefix of environment overrides, nested keys are separated by a double underscore,
+// e.g. IP_RANGES_PROVIDERS__AWS__ENABLED=false
+const ENV_PREFIX: &str = "IP_RANGES_";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub address: IpAddr,
+    pub port: u16,
+    // Seconds between refreshes of each provider
+    pub refresh_interval: u64,
+    // Seconds a single provider fetch may take
+    pub timeout: u64,
+    // Fewest prefixes an update may carry before it is rejected
+    pub min_prefixes: usize,
+    // Largest drop in prefix count, in percent of the served data, an update may make
+    pub max_shrink_percent: f64,
+    // Generations of changes kept per provider, 0 disables the history
+    pub history_limit: usize,
+    // Directory of upstream documents kept current by an external sync job, read instead of
+    // the public URLs of providers without a configured URL
+    pub mirror_dir: Option<PathBuf>,
+    pub http: HttpConfig,
+    pub snapshot: SnapshotConfig,
+    pub webhooks: Vec<WebhookConfig>,
+    pub providers: HashMap<String, ProviderConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            port: 8000,
+            refresh_interval: 300,
+            timeout: 60,
+            min_prefixes: 1,
+            max_shrink_percent: 50.0,
+            history_limit: 100,
+            mirror_dir: None,
+            http: HttpConfig::default(),
+            snapshot: SnapshotConfig::default(),
+            webhooks: Vec::new(),
+            providers: HashMap::new(),
+        }
+    }
+}
+
+// Settings of the HTTP client shared by every provider fetch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    pub user_agent: String,
+    // Seconds to establish a connection
+    pub connect_timeout: u64,
+    // Seconds to wait for each read of a response
+    pub read_timeout: u64,
+    // Retries of a request after connection failures, 429 or 5xx responses
+    pub retries: u32,
+    // Milliseconds before the first retry, doubled on every retry
+    pub retry_delay: u64,
+    // Proxy for every request, otherwise HTTPS_PROXY, HTTP_PROXY and NO_PROXY are followed
+    pub proxy: Option<String>,
+    // Comma-separated hosts that bypass the configured proxy
+    pub no_proxy: Option<String>,
+    // PEM files of extra trusted root certificates
+    pub root_certificates: Vec<PathBuf>,
+    pub max_response_bytes: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            user_agent: format!("ip-ranges-api/{}", env!("CARGO_PKG_VERSION")),
+            connect_timeout: 10,
+            read_timeout: 30,
+            retries: 2,
+            retry_delay: 500,
+            proxy: None,
+            no_proxy: None,
+            root_certificates: Vec::new(),
+            max_response_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+// On-disk copies of the served data, loaded at startup before the first fetch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    pub enabled: bool,
+    pub dir: PathBuf,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        SnapshotConfig {
+            enabled: true,
+            dir: PathBuf::from("snapshots"),
+        }
+    }
+}
+
+// Target notified of the changes of every accepted update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub url: String,
+    // Key signing the payload with HMAC-SHA256, sent in the X-Signature-256 header
+    pub secret: Option<String>,
+    // Comma-separated filters, as in /v1/ranges
+    pub provider: Option<String>,
+    pub family: Option<String>,
+    pub region: Option<String>,
+    pub service: Option<String>,
+    // Retries of a failed delivery
+    pub retries: u32,
+    // Milliseconds before the first retry, doubled on every retry
+    pub retry_delay: u64,
+    // Longest delay between retries in milliseconds
+    pub max_retry_delay: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        WebhookConfig {
+            url: String::new(),
+            secret: None,
+            provider: None,
+            family: None,
+            region: None,
+            service: None,
+            retries: 5,
+            retry_delay: 1000,
+            max_retry_delay: 300_000,
+        }
+    }
+}
+
+// Misspelled keys are rejected rather than silently ignored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProviderConfig {
+    pub enabled: bool,
+    pub refresh_interval: Option<u64>,
+    pub timeout: Option<u64>,
+    pub min_prefixes: Option<usize>,
+    pub max_shrink_percent: Option<f64>,
+    pub url: Option<String>,
+    // Cloudflare publishes each family separately
+    pub ipv4_url: Option<String>,
+    pub ipv6_url: Option<String>,
+    // Azure's service tags file is linked from this page
+    pub download_page_url: Option<String>,
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        ProviderConfig {
+            enabled: true,
+            refresh_interval: None,
+            timeout: None,
+            min_prefixes: None,
+            max_shrink_percent: None,
+            url: None,
+            ipv4_url: None,
+            ipv6_url: None,
+            download_page_url: None,
+        }
+    }
+}
+
+impl ProviderConfig {
+    // Configured upstream URL by its key
+    pub fn url(&self, key: &str) -> Option<&str> {
+        match key {
+            "url" => self.url.as_deref(),
+            "ipv4_url" => self.ipv4_url.as_deref(),
+            "ipv6_url" => self.ipv6_url.as_deref(),
+            "download_page_url" => self.download_page_url.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl Config {
+    // Layer the config file and environment overrides over the defaults
+    #[allow(clippy::result_large_err)]
+    pub fn load() -> Result<Self, rocket::figment::Error> {
+        let path = env::var(format!("{}CONFIG", ENV_PREFIX))
+            .unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+
+        let config: Config = Figment::from(Serialized::defaults(Config::default()))
+            .merge(Toml::file(path))
+            // PORT is kept for compatibility with earlier deployments
+            .merge(Env::raw().only(&["PORT"]))
+            .merge(Env::prefixed(ENV_PREFIX).ignore(&["CONFIG"]).split("__"))
+            .extract()?;
+        config.validate().map_err(rocket::figment::Error::from)?;
+        Ok(config)
+    }
+
+    // Reject zero intervals and timeouts, which would refetch in a hot loop or fail every fetch
+    fn validate(&self) -> Result<(), String> {
+        let mut durations = vec![
+            ("refresh_interval".to_string(), Some(self.refresh_interval)),
+            ("timeout".to_string(), Some(self.timeout)),
+            (
+                "http.connect_timeout".to_string(),
+                Some(self.http.connect_timeout),
+            ),
+            (
+                "http.read_timeout".to_string(),
+                Some(self.http.read_timeout),
+            ),
+        ];
+        let mut providers: Vec<(&String, &ProviderConfig)> = self.providers.iter().collect();
+        providers.sort_by_key(|(name, _)| name.as_str());
+        for (name, provider) in providers {
+            durations.push((
+                format!("providers.{}.refresh_interval", name),
+                provider.refresh_interval,
+            ));
+            durations.push((format!("providers.{}.timeout", name), provider.timeout));
+        }
+
+        match durations.into_iter().find(|(_, value)| *value == Some(0)) {
+            Some((key, _)) => Err(format!("{} must be greater than 0", key)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn provider(&self, integration_name: &str) -> Option<&ProviderConfig> {
+        self.providers.get(integration_name)
+    }
+
+    pub fn provider_enabled(&self, integration_name: &str) -> bool {
+        self.provider(integration_name)
+            .is_none_or(|provider| provider.enabled)
+    }
+
+    pub fn refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.refresh_interval)
+    }
+
+    // Refresh interval set for the provider, if any
+    pub fn provider_refresh_interval(&self, integration_name: &str) -> Option<Duration> {
+        self.provider(integration_name)
+            .and_then(|provider| provider.refresh_interval)
+            .map(Duration::from_secs)
+    }
+
+    pub fn provider_timeout(&self, integration_name: &str) -> Duration {
+        let timeout = self
+            .provider(integration_name)
+            .and_then(|provider| provider.timeout)
+            .unwrap_or(self.timeout);
+        Duration::from_secs(timeout)
+    }
+
+    pub fn provider_min_prefixes(&self, integration_name: &str) -> usize {
+        self.provider(integration_name)
+            .and_then(|provider| provider.min_prefixes)
+            .unwrap_or(self.min_prefixes)
+    }
+
+    pub fn provider_max_shrink_percent(&self, integration_name: &str) -> f64 {
+        self.provider(integration_name)
+            .and_then(|provider| provider.max_shrink_percent)
+            .unwrap_or(self.max_shrink_percent)
+    }
+
+    // Configured URL for one of the provider's upstreams
+    pub fn provider_url(&self, integration_name: &str, key: &str) -> Option<&str> {
+        self.provider(integration_name)
+            .and_then(|provider| provider.url(key))
+    }
+}
+
+lazy_static! {
+    static ref CONFIG: Config =
+        Config::load().unwrap_or_else(|err| panic!("Invalid configuration: {}", err));
+}
+
+pub fn get() -> &'static Config {
+    &CONFIG
+}
+
+// Source configured for a provider upstream: its own URL, or its file in the mirror directory
+pub fn configured_url(integration_name: &str, key: &str, mirror_file: &str) -> Option<String> {
+    let settings = get();
+    settings
+        .provider_url(integration_name, key)
+        .map(str::to_string)
+        .or_else(|| {
+            settings
+                .mirror_dir
+                .as_ref()
+                .map(|dir| dir.join(mirror_file).display().to_string())
+        })
+}
+
+// Source of a provider upstream, falling back to the public default
+pub fn upstream_url(integration_name: &str, key: &str, default: &str, mirror_file: &str) -> String {
+    configured_url(integration_name, key, mirror_file).unwrap_or_else(|| default.to_string())
+}
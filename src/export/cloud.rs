@@ -0,0 +1,166 @@
+use crate::export::ExportContext;
+use ipnet::IpNet;
+use rocket::serde::json::serde_json::{self, json, Value};
+
+// Default entries per chunk, the limits each cloud applies by default
+const AWS_SECURITY_GROUP_MAX_ENTRIES: usize = 60;
+const AWS_PREFIX_LIST_MAX_ENTRIES: usize = 1000;
+const GCP_FIREWALL_MAX_ENTRIES: usize = 5000;
+const AZURE_NSG_MAX_ENTRIES: usize = 4000;
+
+// Priority of the first Azure rule, later chunks counting up from it
+const AZURE_NSG_FIRST_PRIORITY: usize = 100;
+
+// Longest descriptions each cloud accepts
+const AWS_DESCRIPTION_LENGTH: usize = 255;
+const GCP_DESCRIPTION_LENGTH: usize = 2048;
+const AZURE_DESCRIPTION_LENGTH: usize = 140;
+
+// Prefixes of each family with the name they are exported under, skipping empty families.
+// Clouds do not mix families in a prefix list or rule.
+fn families(context: &ExportContext) -> impl Iterator<Item = (&str, &[IpNet])> {
+    [
+        ("ipv4", context.ipv4.as_slice()),
+        ("ipv6", context.ipv6.as_slice()),
+    ]
+    .into_iter()
+    .filter(|(_, prefixes)| !prefixes.is_empty())
+}
+
+fn max_entries(context: &ExportContext, default: usize) -> usize {
+    context.options.max_entries_per_rule.unwrap_or(default)
+}
+
+// Description of a chunk, recording where its prefixes came from
+fn description(context: &ExportContext, part: usize, parts: usize, max_length: usize) -> String {
+    let mut description = format!("{} part {} of {}", context.summary(), part, parts);
+    if description.len() > max_length {
+        let mut end = max_length;
+        while !description.is_char_boundary(end) {
+            end -= 1;
+        }
+        description.truncate(end);
+    }
+    description
+}
+
+fn pretty(value: Value) -> String {
+    serde_json::to_string_pretty(&value).unwrap_or_default() + "\n"
+}
+
+// Security groups for `aws ec2 create-security-group` and `authorize-security-group-ingress`,
+// every prefix counting as one rule
+pub fn aws_security_group(context: &ExportContext) -> String {
+    let prefixes: Vec<IpNet> = context.prefixes().copied().collect();
+    let chunks: Vec<&[IpNet]> = prefixes
+        .chunks(max_entries(context, AWS_SECURITY_GROUP_MAX_ENTRIES))
+        .collect();
+
+    let groups: Vec<Value> = chunks
+        .iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let description = description(context, index + 1, chunks.len(), AWS_DESCRIPTION_LENGTH);
+            let ipv4: Vec<Value> = chunk
+                .iter()
+                .filter(|prefix| matches!(prefix, IpNet::V4(_)))
+                .map(|prefix| json!({ "CidrIp": prefix.to_string(), "Description": description }))
+                .collect();
+            let ipv6: Vec<Value> = chunk
+                .iter()
+                .filter(|prefix| matches!(prefix, IpNet::V6(_)))
+                .map(|prefix| json!({ "CidrIpv6": prefix.to_string(), "Description": description }))
+                .collect();
+            json!({
+                "GroupName": format!("{}-{}", context.name, index + 1),
+                "Description": description,
+                "IpPermissions": [{
+                    "IpProtocol": "-1",
+                    "IpRanges": ipv4,
+                    "Ipv6Ranges": ipv6,
+                }],
+            })
+        })
+        .collect();
+    pretty(Value::Array(groups))
+}
+
+// Managed prefix lists for `aws ec2 create-managed-prefix-list`, one family each
+pub fn aws_prefix_list(context: &ExportContext) -> String {
+    let mut lists = Vec::new();
+    for (family, prefixes) in families(context) {
+        let chunks: Vec<&[IpNet]> = prefixes
+            .chunks(max_entries(context, AWS_PREFIX_LIST_MAX_ENTRIES))
+            .collect();
+        for (index, chunk) in chunks.iter().enumerate() {
+            let description = description(context, index + 1, chunks.len(), AWS_DESCRIPTION_LENGTH);
+            let entries: Vec<Value> = chunk
+                .iter()
+                .map(|prefix| json!({ "Cidr": prefix.to_string(), "Description": description }))
+                .collect();
+            lists.push(json!({
+                "PrefixListName": format!("{}-{}-{}", context.name, family, index + 1),
+                "AddressFamily": if family == "ipv4" { "IPv4" } else { "IPv6" },
+                "MaxEntries": chunk.len(),
+                "Entries": entries,
+            }));
+        }
+    }
+    pretty(Value::Array(lists))
+}
+
+// Ingress firewall rules for the Compute Engine API, whose names only allow lowercase letters,
+// digits and hyphens
+pub fn gcp_firewall(context: &ExportContext) -> String {
+    let name = context.resource_name();
+    let mut rules = Vec::new();
+    for (family, prefixes) in families(context) {
+        let chunks: Vec<&[IpNet]> = prefixes
+            .chunks(max_entries(context, GCP_FIREWALL_MAX_ENTRIES))
+            .collect();
+        for (index, chunk) in chunks.iter().enumerate() {
+            let source_ranges: Vec<String> = chunk.iter().map(IpNet::to_string).collect();
+            rules.push(json!({
+                "name": format!("{}-{}-{}", name, family, index + 1),
+                "description": description(context, index + 1, chunks.len(), GCP_DESCRIPTION_LENGTH),
+                "direction": "INGRESS",
+                "allowed": [{ "IPProtocol": "all" }],
+                "sourceRanges": source_ranges,
+            }));
+        }
+    }
+    pretty(Value::Array(rules))
+}
+
+// Inbound network security group rules, with consecutive priorities
+pub fn azure_nsg(context: &ExportContext) -> String {
+    let mut rules = Vec::new();
+    for (family, prefixes) in families(context) {
+        let chunks: Vec<&[IpNet]> = prefixes
+            .chunks(max_entries(context, AZURE_NSG_MAX_ENTRIES))
+            .collect();
+        for (index, chunk) in chunks.iter().enumerate() {
+            let source_prefixes: Vec<String> = chunk.iter().map(IpNet::to_string).collect();
+            rules.push(json!({
+                "name": format!("{}-{}-{}", context.name, family, index + 1),
+                "properties": {
+                    "description": description(
+                        context,
+                        index + 1,
+                        chunks.len(),
+                        AZURE_DESCRIPTION_LENGTH,
+                    ),
+                    "priority": AZURE_NSG_FIRST_PRIORITY + rules.len(),
+                    "direction": "Inbound",
+                    "access": "Allow",
+                    "protocol": "*",
+                    "sourceAddressPrefixes": source_prefixes,
+                    "sourcePortRange": "*",
+                    "destinationAddressPrefix": "*",
+                    "destinationPortRange": "*",
+                },
+            }));
+        }
+    }
+    pretty(Value::Array(rules))
+}
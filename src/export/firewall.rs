@@ -0,0 +1,113 @@
+use crate::export::ExportContext;
+use ipnet::IpNet;
+
+// ipset's default set size, raised for larger exports
+const IPSET_DEFAULT_MAXELEM: usize = 65536;
+
+// Prefixes of each family with the set suffix they are exported under, skipping empty families
+fn families(context: &ExportContext) -> impl Iterator<Item = (bool, &str, &[IpNet])> {
+    [
+        (false, "v4", context.ipv4.as_slice()),
+        (true, "v6", context.ipv6.as_slice()),
+    ]
+    .into_iter()
+    .filter(|(_, _, prefixes)| !prefixes.is_empty())
+}
+
+// One interval set per family, indented to the given depth
+fn nftables_sets(context: &ExportContext, indent: &str) -> String {
+    let mut output = String::new();
+    for (ipv6, suffix, prefixes) in families(context) {
+        output.push_str(&format!("{indent}set {} {{\n", context.family_name(suffix)));
+        output.push_str(&format!(
+            "{indent}\ttype {}\n",
+            if ipv6 { "ipv6_addr" } else { "ipv4_addr" }
+        ));
+        output.push_str(&format!("{indent}\tflags interval\n"));
+        output.push_str(&format!("{indent}\tauto-merge\n"));
+        output.push_str(&format!("{indent}\telements = {{\n"));
+        for (position, prefix) in prefixes.iter().enumerate() {
+            let separator = if position + 1 < prefixes.len() {
+                ","
+            } else {
+                ""
+            };
+            output.push_str(&format!("{indent}\t\t{}{}\n", prefix, separator));
+        }
+        output.push_str(&format!("{indent}\t}}\n"));
+        output.push_str(&format!("{indent}}}\n"));
+    }
+    output
+}
+
+pub fn nftables(context: &ExportContext) -> String {
+    context.header("#") + &nftables_sets(context, "")
+}
+
+// Declaring and deleting the table first lets `nft -f` replace it atomically
+pub fn nftables_table(context: &ExportContext) -> String {
+    let mut output = context.header("#");
+    output.push_str(&format!("table inet {}\n", context.name));
+    output.push_str(&format!("delete table inet {}\n", context.name));
+    output.push_str(&format!("table inet {} {{\n", context.name));
+    output.push_str(&nftables_sets(context, "\t"));
+    output.push_str("}\n");
+    output
+}
+
+pub fn ipset(context: &ExportContext) -> String {
+    let mut output = context.header("#");
+    for (ipv6, suffix, prefixes) in families(context) {
+        let name = context.family_name(suffix);
+        output.push_str(&format!(
+            "create {} hash:net family {} maxelem {} -exist\n",
+            name,
+            if ipv6 { "inet6" } else { "inet" },
+            prefixes.len().max(IPSET_DEFAULT_MAXELEM)
+        ));
+        output.push_str(&format!("flush {}\n", name));
+        for prefix in prefixes {
+            output.push_str(&format!("add {} {} -exist\n", name, prefix));
+        }
+    }
+    output
+}
+
+// A chain accepting the prefixes, created or flushed, for each family's command
+pub fn iptables(context: &ExportContext) -> String {
+    let mut output = context.header("#");
+    for (ipv6, _, prefixes) in families(context) {
+        let command = if ipv6 { "ip6tables" } else { "iptables" };
+        output.push_str(&format!(
+            "{command} -N {name} 2>/dev/null || {command} -F {name}\n",
+            name = context.name
+        ));
+        for prefix in prefixes {
+            output.push_str(&format!(
+                "{} -A {} -s {} -j ACCEPT\n",
+                command, context.name, prefix
+            ));
+        }
+    }
+    output
+}
+
+pub fn pf(context: &ExportContext) -> String {
+    let mut output = context.header("#");
+    for (_, suffix, prefixes) in families(context) {
+        output.push_str(&format!(
+            "table <{}> persist {{ \\\n",
+            context.family_name(suffix)
+        ));
+        for (position, prefix) in prefixes.iter().enumerate() {
+            let separator = if position + 1 < prefixes.len() {
+                ","
+            } else {
+                ""
+            };
+            output.push_str(&format!("\t{}{} \\\n", prefix, separator));
+        }
+        output.push_str("}\n");
+    }
+    output
+}
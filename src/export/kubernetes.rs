@@ -0,0 +1,99 @@
+use crate::export::{Direction, ExportContext};
+
+// Namespace of namespaced policies when none is given
+const DEFAULT_NAMESPACE: &str = "default";
+
+// Metadata of a namespaced resource, with the header recording its source
+fn namespaced(context: &ExportContext, api_version: &str, kind: &str) -> String {
+    let mut output = context.stable_header("#");
+    output.push_str(&format!("apiVersion: {}\n", api_version));
+    output.push_str(&format!("kind: {}\n", kind));
+    output.push_str("metadata:\n");
+    output.push_str(&format!("  name: {}\n", context.resource_name()));
+    output.push_str(&format!(
+        "  namespace: {}\n",
+        context
+            .options
+            .namespace
+            .as_deref()
+            .unwrap_or(DEFAULT_NAMESPACE)
+    ));
+    output
+}
+
+// A NetworkPolicy selecting every pod of the namespace and allowing traffic from or to the
+// prefixes on the given ports
+pub fn network_policy(context: &ExportContext) -> String {
+    let (policy_type, rules, peers) = match context.options.direction {
+        Direction::Ingress => ("Ingress", "ingress", "from"),
+        Direction::Egress => ("Egress", "egress", "to"),
+    };
+
+    let mut output = namespaced(context, "networking.k8s.io/v1", "NetworkPolicy");
+    output.push_str("spec:\n");
+    output.push_str("  podSelector: {}\n");
+    output.push_str("  policyTypes:\n");
+    output.push_str(&format!("    - {}\n", policy_type));
+    output.push_str(&format!("  {}:\n", rules));
+    output.push_str(&format!("    - {}:\n", peers));
+    for prefix in context.prefixes() {
+        output.push_str("        - ipBlock:\n");
+        output.push_str(&format!("            cidr: \"{}\"\n", prefix));
+    }
+    if !context.options.ports.is_empty() {
+        output.push_str("      ports:\n");
+        for port in &context.options.ports {
+            output.push_str(&format!("        - protocol: {}\n", port.protocol));
+            output.push_str(&format!("          port: {}\n", port.port));
+        }
+    }
+    output
+}
+
+// A cluster-wide set of the prefixes, selected by Calico policies through its name label
+pub fn calico_global_network_set(context: &ExportContext) -> String {
+    let mut output = context.stable_header("#");
+    output.push_str("apiVersion: projectcalico.org/v3\n");
+    output.push_str("kind: GlobalNetworkSet\n");
+    output.push_str("metadata:\n");
+    output.push_str(&format!("  name: {}\n", context.resource_name()));
+    output.push_str("  labels:\n");
+    output.push_str(&format!(
+        "    {}/name: {}\n",
+        env!("CARGO_PKG_NAME"),
+        context.resource_name()
+    ));
+    output.push_str("spec:\n");
+    output.push_str("  nets:\n");
+    for prefix in context.prefixes() {
+        output.push_str(&format!("    - \"{}\"\n", prefix));
+    }
+    output
+}
+
+// A CiliumNetworkPolicy selecting every endpoint of the namespace and allowing traffic from or to
+// the prefixes on the given ports
+pub fn cilium_network_policy(context: &ExportContext) -> String {
+    let (rules, cidr_set) = match context.options.direction {
+        Direction::Ingress => ("ingress", "fromCIDRSet"),
+        Direction::Egress => ("egress", "toCIDRSet"),
+    };
+
+    let mut output = namespaced(context, "cilium.io/v2", "CiliumNetworkPolicy");
+    output.push_str("spec:\n");
+    output.push_str("  endpointSelector: {}\n");
+    output.push_str(&format!("  {}:\n", rules));
+    output.push_str(&format!("    - {}:\n", cidr_set));
+    for prefix in context.prefixes() {
+        output.push_str(&format!("        - cidr: \"{}\"\n", prefix));
+    }
+    if !context.options.ports.is_empty() {
+        output.push_str("      toPorts:\n");
+        output.push_str("        - ports:\n");
+        for port in &context.options.ports {
+            output.push_str(&format!("            - port: \"{}\"\n", port.port));
+            output.push_str(&format!("              protocol: {}\n", port.protocol));
+        }
+    }
+    output
+}
@@ -0,0 +1,459 @@
+pub mod cloud;
+pub mod firewall;
+pub mod kubernetes;
+pub mod proxy;
+
+use crate::cache;
+use crate::fetchers::normalized::NormalizedPrefix;
+use crate::fetchers::registry;
+use ipnet::IpNet;
+use rocket::http::ContentType;
+use std::collections::BTreeSet;
+
+// Longest Kubernetes namespace name
+const MAX_NAMESPACE_LENGTH: usize = 63;
+
+// Longest set name accepted, leaving room for the family suffix within the 31 characters ipset
+// and pf allow
+const MAX_NAME_LENGTH: usize = 25;
+
+// Configuration formats the prefixes of a query can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    // Named nftables sets, to include inside an existing table
+    Nftables,
+    // An nftables table holding the sets, replacing any previous version of it
+    NftablesTable,
+    // An `ipset restore` file
+    Ipset,
+    // iptables and ip6tables commands filling a chain
+    Iptables,
+    // pf tables
+    Pf,
+    // nginx allow rules followed by deny all
+    NginxAllow,
+    // nginx deny rules
+    NginxDeny,
+    // nginx set_real_ip_from directives
+    NginxRealip,
+    // A HAProxy ACL file
+    Haproxy,
+    // Apache Require ip rules
+    Apache,
+    // A Caddy trusted_proxies option
+    Caddy,
+    // An Envoy RBAC filter
+    Envoy,
+    // AWS security groups
+    AwsSecurityGroup,
+    // AWS managed prefix lists
+    AwsPrefixList,
+    // GCP firewall rules
+    GcpFirewall,
+    // Azure network security group rules
+    AzureNsg,
+    // A Kubernetes NetworkPolicy with ipBlock peers
+    KubernetesNetworkPolicy,
+    // A Calico GlobalNetworkSet
+    CalicoGlobalNetworkSet,
+    // A CiliumNetworkPolicy with CIDR sets
+    CiliumNetworkPolicy,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "nftables" | "nft" => Some(ExportFormat::Nftables),
+            "nftables-table" | "nft-table" => Some(ExportFormat::NftablesTable),
+            "ipset" => Some(ExportFormat::Ipset),
+            "iptables" => Some(ExportFormat::Iptables),
+            "pf" => Some(ExportFormat::Pf),
+            "nginx-allow" | "nginx" => Some(ExportFormat::NginxAllow),
+            "nginx-deny" => Some(ExportFormat::NginxDeny),
+            "nginx-realip" => Some(ExportFormat::NginxRealip),
+            "haproxy" => Some(ExportFormat::Haproxy),
+            "apache" => Some(ExportFormat::Apache),
+            "caddy" => Some(ExportFormat::Caddy),
+            "envoy" => Some(ExportFormat::Envoy),
+            "aws-security-group" | "aws-sg" => Some(ExportFormat::AwsSecurityGroup),
+            "aws-prefix-list" => Some(ExportFormat::AwsPrefixList),
+            "gcp-firewall" => Some(ExportFormat::GcpFirewall),
+            "azure-nsg" => Some(ExportFormat::AzureNsg),
+            "k8s-networkpolicy" | "networkpolicy" => Some(ExportFormat::KubernetesNetworkPolicy),
+            "calico-globalnetworkset" | "calico" => Some(ExportFormat::CalicoGlobalNetworkSet),
+            "cilium-networkpolicy" | "cilium" => Some(ExportFormat::CiliumNetworkPolicy),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Nftables => "nftables",
+            ExportFormat::NftablesTable => "nftables-table",
+            ExportFormat::Ipset => "ipset",
+            ExportFormat::Iptables => "iptables",
+            ExportFormat::Pf => "pf",
+            ExportFormat::NginxAllow => "nginx-allow",
+            ExportFormat::NginxDeny => "nginx-deny",
+            ExportFormat::NginxRealip => "nginx-realip",
+            ExportFormat::Haproxy => "haproxy",
+            ExportFormat::Apache => "apache",
+            ExportFormat::Caddy => "caddy",
+            ExportFormat::Envoy => "envoy",
+            ExportFormat::AwsSecurityGroup => "aws-security-group",
+            ExportFormat::AwsPrefixList => "aws-prefix-list",
+            ExportFormat::GcpFirewall => "gcp-firewall",
+            ExportFormat::AzureNsg => "azure-nsg",
+            ExportFormat::KubernetesNetworkPolicy => "k8s-networkpolicy",
+            ExportFormat::CalicoGlobalNetworkSet => "calico-globalnetworkset",
+            ExportFormat::CiliumNetworkPolicy => "cilium-networkpolicy",
+        }
+    }
+
+    // Formats naming Kubernetes or Compute Engine resources, whose names must end with a letter or
+    // digit, unlike set and chain names
+    fn names_resource(self) -> bool {
+        matches!(
+            self,
+            ExportFormat::GcpFirewall
+                | ExportFormat::KubernetesNetworkPolicy
+                | ExportFormat::CalicoGlobalNetworkSet
+                | ExportFormat::CiliumNetworkPolicy
+        )
+    }
+
+    pub fn content_type(self) -> ContentType {
+        match self {
+            ExportFormat::AwsSecurityGroup
+            | ExportFormat::AwsPrefixList
+            | ExportFormat::GcpFirewall
+            | ExportFormat::AzureNsg => ContentType::JSON,
+            _ => ContentType::Plain,
+        }
+    }
+}
+
+// Direction of the traffic a policy allows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Direction {
+    #[default]
+    Ingress,
+    Egress,
+}
+
+// A destination port a policy allows, with its protocol in uppercase
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Port {
+    pub port: u16,
+    pub protocol: String,
+}
+
+// Export parameters read from the query string
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    pub name: Option<String>,
+    // Prefixes per rule, group or list of the cloud formats, which split larger exports
+    pub max_entries_per_rule: Option<usize>,
+    // Namespace of namespaced Kubernetes policies
+    pub namespace: Option<String>,
+    pub direction: Direction,
+    // Ports of the Kubernetes policies, sorted, all ports when empty
+    pub ports: Vec<Port>,
+}
+
+impl ExportOptions {
+    // Read the options through a query parameter lookup, rejecting invalid values
+    pub fn parse<'a>(
+        format: ExportFormat,
+        param: impl Fn(&str) -> Option<&'a str>,
+    ) -> Result<Self, String> {
+        let name = param("name").map(str::to_string);
+        if let Some(name) = &name {
+            let valid = name.len() <= MAX_NAME_LENGTH
+                && name.starts_with(|c: char| c.is_ascii_alphabetic())
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            if !valid {
+                return Err(format!(
+                    "Invalid name: {} (use up to {} letters, digits, _ or -, starting with a letter)",
+                    name, MAX_NAME_LENGTH
+                ));
+            }
+            if format.names_resource() && !name.ends_with(|c: char| c.is_ascii_alphanumeric()) {
+                return Err(format!(
+                    "Invalid name: {} ({} resource names must end with a letter or digit)",
+                    name,
+                    format.name()
+                ));
+            }
+        }
+
+        let max_entries_per_rule = param("max_entries_per_rule")
+            .map(|value| match value.parse::<usize>() {
+                Ok(max) if max > 0 => Ok(max),
+                _ => Err(format!("Invalid value for max_entries_per_rule: {}", value)),
+            })
+            .transpose()?;
+
+        let namespace = param("namespace").map(str::to_string);
+        if let Some(namespace) = &namespace {
+            let valid = namespace.len() <= MAX_NAMESPACE_LENGTH
+                && namespace.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+                && namespace.ends_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+                && namespace
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+            if !valid {
+                return Err(format!("Invalid namespace: {}", namespace));
+            }
+        }
+
+        let direction = match param("direction").map(str::to_lowercase).as_deref() {
+            None | Some("ingress") => Direction::Ingress,
+            Some("egress") => Direction::Egress,
+            Some(direction) => return Err(format!("Invalid direction: {}", direction)),
+        };
+
+        // Ports are given as a comma-separated list of port[/protocol], TCP by default
+        let mut ports = param("ports")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|port| !port.is_empty())
+            .map(|port| {
+                let (number, protocol) = port.split_once('/').unwrap_or((port, "tcp"));
+                let protocol = protocol.to_uppercase();
+                match number.parse::<u16>() {
+                    Ok(number)
+                        if number > 0 && ["TCP", "UDP", "SCTP"].contains(&protocol.as_str()) =>
+                    {
+                        Ok(Port {
+                            port: number,
+                            protocol,
+                        })
+                    }
+                    _ => Err(format!("Invalid port: {}", port)),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        ports.sort();
+        ports.dedup();
+
+        Ok(ExportOptions {
+            name,
+            max_entries_per_rule,
+            namespace,
+            direction,
+            ports,
+        })
+    }
+}
+
+// Everything a format renders: the deduplicated prefixes of each family and where they came from
+pub struct ExportContext {
+    pub format: ExportFormat,
+    pub options: ExportOptions,
+    pub name: String,
+    pub providers: Vec<String>,
+    pub filters: Vec<(String, String)>,
+    pub ipv4: Vec<IpNet>,
+    pub ipv6: Vec<IpNet>,
+}
+
+impl ExportContext {
+    // Comment lines recording the format, providers with their data versions, and filters
+    pub fn header(&self, comment: &str) -> String {
+        self.header_lines(comment, true)
+    }
+
+    // The header without fetch times, which change on every refresh of a provider publishing no
+    // version, so unchanged data renders identically
+    pub fn stable_header(&self, comment: &str) -> String {
+        self.header_lines(comment, false)
+    }
+
+    fn header_lines(&self, comment: &str, fetch_times: bool) -> String {
+        let providers: Vec<String> = self
+            .providers
+            .iter()
+            .map(|provider| match cache::get(provider) {
+                Some(cached) => match cached.version.as_ref() {
+                    Some(version) if version.sync_token.is_some() || version.created.is_some() => {
+                        let mut details = Vec::new();
+                        if let Some(sync_token) = &version.sync_token {
+                            details.push(format!("sync token {}", sync_token));
+                        }
+                        if let Some(created) = &version.created {
+                            details.push(format!("created {}", created));
+                        }
+                        format!("{} ({})", provider, details.join(", "))
+                    }
+                    _ if fetch_times => format!(
+                        "{} (fetched {})",
+                        provider,
+                        cached.fetched_at.format("%Y-%m-%dT%H:%M:%SZ")
+                    ),
+                    _ => provider.clone(),
+                },
+                None => provider.clone(),
+            })
+            .collect();
+        let filters: Vec<String> = self
+            .filters
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+
+        let mut header = format!(
+            "{} Generated by {} as {}\n",
+            comment,
+            env!("CARGO_PKG_NAME"),
+            self.format.name()
+        );
+        header.push_str(&format!(
+            "{} Providers: {}\n",
+            comment,
+            providers.join(", ")
+        ));
+        header.push_str(&format!(
+            "{} Filters: {}\n",
+            comment,
+            if filters.is_empty() {
+                "none".to_string()
+            } else {
+                filters.join(", ")
+            }
+        ));
+        header.push_str(&format!(
+            "{} Prefixes: {} IPv4, {} IPv6\n",
+            comment,
+            self.ipv4.len(),
+            self.ipv6.len()
+        ));
+        header
+    }
+
+    // One-line account of the providers and filters, for formats without comments
+    pub fn summary(&self) -> String {
+        let filters: Vec<String> = self
+            .filters
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        if filters.is_empty() {
+            format!("{} ranges", self.providers.join(", "))
+        } else {
+            format!(
+                "{} ranges ({})",
+                self.providers.join(", "),
+                filters.join(", ")
+            )
+        }
+    }
+
+    // Prefixes of both families, IPv4 first
+    pub fn prefixes(&self) -> impl Iterator<Item = &IpNet> {
+        self.ipv4.iter().chain(self.ipv6.iter())
+    }
+
+    // The name in the lowercase letters, digits and hyphens cloud and Kubernetes resources allow
+    pub fn resource_name(&self) -> String {
+        self.name.to_lowercase().replace('_', "-")
+    }
+
+    // Name of the set holding the prefixes of one family
+    pub fn family_name(&self, suffix: &str) -> String {
+        format!("{}_{}", self.name, suffix)
+    }
+}
+
+// Render the prefixes of a query in an export format. The filters are the parameters the prefixes
+// were selected with, recorded with control characters escaped so they cannot break out of a
+// comment line.
+pub fn render<'a>(
+    format: ExportFormat,
+    options: &ExportOptions,
+    filters: &[(&str, &str)],
+    prefixes: impl IntoIterator<Item = &'a NormalizedPrefix>,
+) -> String {
+    let mut providers = BTreeSet::new();
+    let mut ipv4 = BTreeSet::new();
+    let mut ipv6 = BTreeSet::new();
+    for prefix in prefixes {
+        providers.insert(prefix.provider.as_str());
+        match prefix.network {
+            IpNet::V4(_) => ipv4.insert(prefix.network),
+            IpNet::V6(_) => ipv6.insert(prefix.network),
+        };
+    }
+
+    // List providers in registry order
+    let providers: Vec<String> = registry()
+        .iter()
+        .map(|integration| integration.name())
+        .filter(|name| providers.contains(name))
+        .map(str::to_string)
+        .collect();
+
+    let mut filters: Vec<(String, String)> = filters
+        .iter()
+        .map(|(key, value)| (escape_control(key), escape_control(value)))
+        .collect();
+    filters.sort();
+
+    // A single provider names the sets after itself
+    let name = options
+        .name
+        .clone()
+        .unwrap_or_else(|| match providers.as_slice() {
+            [provider] => provider.clone(),
+            _ => "cloud_ranges".to_string(),
+        });
+
+    let context = ExportContext {
+        format,
+        options: options.clone(),
+        name,
+        providers,
+        filters,
+        ipv4: ipv4.into_iter().collect(),
+        ipv6: ipv6.into_iter().collect(),
+    };
+
+    match format {
+        ExportFormat::Nftables => firewall::nftables(&context),
+        ExportFormat::NftablesTable => firewall::nftables_table(&context),
+        ExportFormat::Ipset => firewall::ipset(&context),
+        ExportFormat::Iptables => firewall::iptables(&context),
+        ExportFormat::Pf => firewall::pf(&context),
+        ExportFormat::NginxAllow => proxy::nginx_allow(&context),
+        ExportFormat::NginxDeny => proxy::nginx_deny(&context),
+        ExportFormat::NginxRealip => proxy::nginx_realip(&context),
+        ExportFormat::Haproxy => proxy::haproxy(&context),
+        ExportFormat::Apache => proxy::apache(&context),
+        ExportFormat::Caddy => proxy::caddy(&context),
+        ExportFormat::Envoy => proxy::envoy(&context),
+        ExportFormat::AwsSecurityGroup => cloud::aws_security_group(&context),
+        ExportFormat::AwsPrefixList => cloud::aws_prefix_list(&context),
+        ExportFormat::GcpFirewall => cloud::gcp_firewall(&context),
+        ExportFormat::AzureNsg => cloud::azure_nsg(&context),
+        ExportFormat::KubernetesNetworkPolicy => kubernetes::network_policy(&context),
+        ExportFormat::CalicoGlobalNetworkSet => kubernetes::calico_global_network_set(&context),
+        ExportFormat::CiliumNetworkPolicy => kubernetes::cilium_network_policy(&context),
+    }
+}
+
+// Replace control characters such as line breaks with their escaped form
+fn escape_control(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_control() {
+                c.escape_default().to_string()
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,82 @@
+use crate::export::ExportContext;
+use ipnet::IpNet;
+
+// nginx access rules allowing the prefixes and denying everything else
+pub fn nginx_allow(context: &ExportContext) -> String {
+    let mut output = context.header("#");
+    for prefix in context.prefixes() {
+        output.push_str(&format!("allow {};\n", prefix));
+    }
+    output.push_str("deny all;\n");
+    output
+}
+
+pub fn nginx_deny(context: &ExportContext) -> String {
+    let mut output = context.header("#");
+    for prefix in context.prefixes() {
+        output.push_str(&format!("deny {};\n", prefix));
+    }
+    output
+}
+
+// Proxies nginx takes the client address from. The real_ip_header directive is left to the
+// including configuration, so snippets of several providers can be combined.
+pub fn nginx_realip(context: &ExportContext) -> String {
+    let mut output = context.header("#");
+    for prefix in context.prefixes() {
+        output.push_str(&format!("set_real_ip_from {};\n", prefix));
+    }
+    output
+}
+
+// A HAProxy ACL file, loaded with `src -f <file>`
+pub fn haproxy(context: &ExportContext) -> String {
+    let mut output = context.header("#");
+    for prefix in context.prefixes() {
+        output.push_str(&format!("{}\n", prefix));
+    }
+    output
+}
+
+// Apache 2.4 authorization rules, any of which grants access
+pub fn apache(context: &ExportContext) -> String {
+    let mut output = context.header("#");
+    for prefix in context.prefixes() {
+        output.push_str(&format!("Require ip {}\n", prefix));
+    }
+    output
+}
+
+// A Caddy trusted_proxies server option, imported inside the servers global option
+pub fn caddy(context: &ExportContext) -> String {
+    let prefixes: Vec<String> = context.prefixes().map(IpNet::to_string).collect();
+    context.header("#") + &format!("trusted_proxies static {}\n", prefixes.join(" "))
+}
+
+// An Envoy RBAC HTTP filter allowing connections from the prefixes
+pub fn envoy(context: &ExportContext) -> String {
+    let mut output = context.header("#");
+    output.push_str("name: envoy.filters.http.rbac\n");
+    output.push_str("typed_config:\n");
+    output
+        .push_str("  \"@type\": type.googleapis.com/envoy.extensions.filters.http.rbac.v3.RBAC\n");
+    output.push_str("  rules:\n");
+    output.push_str("    action: ALLOW\n");
+    output.push_str("    policies:\n");
+    output.push_str(&format!("      {}:\n", context.name));
+    output.push_str("        permissions:\n");
+    output.push_str("          - any: true\n");
+    output.push_str("        principals:\n");
+    for prefix in context.prefixes() {
+        output.push_str("          - direct_remote_ip:\n");
+        output.push_str(&format!(
+            "              address_prefix: \"{}\"\n",
+            prefix.network()
+        ));
+        output.push_str(&format!(
+            "              prefix_len: {}\n",
+            prefix.prefix_len()
+        ));
+    }
+    output
+}
@@ -1,15 +1,35 @@
-use super::Integration;
-use crate::cache::IntegrationCache;
+use super::error::FetchError;
+use super::http::HttpClient;
+use super::normalized::{Family, NormalizedPrefix, PrefixError};
+use super::{sync_token_unchanged, FetchOutcome, FetchedData, Integration, UpstreamVersion};
+use crate::config;
+use crate::filter::{FamilyPolicy, FilterField, FilterMatch, FilterParam, FilterSchema};
 use async_trait::async_trait;
-use reqwest;
 use rocket::serde::json::serde_json;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
-use tracing::{error, info};
+use tracing::info;
 use uuid::Uuid;
 
+const AWS_URL: &str = "https://ip-ranges.amazonaws.com/ip-ranges.json";
+const AWS_MIRROR_FILE: &str = "aws.json";
+
+// Only the sync token of the document, read before deciding on a full parse
+#[derive(Debug, Deserialize)]
+struct AwsSyncToken {
+    #[serde(rename = "syncToken")]
+    sync_token: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct AwsIpRanges {
+    #[serde(rename = "syncToken")]
+    pub sync_token: Option<String>,
+    #[serde(rename = "createDate")]
+    pub create_date: Option<String>,
     pub prefixes: Vec<AwsPrefix>,
+    #[serde(default)]
+    pub ipv6_prefixes: Vec<AwsIpv6Prefix>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -20,50 +40,118 @@ pub struct AwsPrefix {
     pub network_border_group: String,
 }
 
-pub struct AwsIntegration {
-    execution_id: Uuid,
+#[derive(Debug, Deserialize, Clone)]
+pub struct AwsIpv6Prefix {
+    pub ipv6_prefix: String,
+    pub region: String,
+    pub service: String,
+    pub network_border_group: String,
 }
 
+const FILTERS: &[FilterParam] = &[
+    FilterParam {
+        name: "region",
+        field: FilterField::Region,
+        matching: FilterMatch::Exact,
+    },
+    FilterParam {
+        name: "service",
+        field: FilterField::Service,
+        matching: FilterMatch::Exact,
+    },
+    FilterParam {
+        name: "network_border_group",
+        field: FilterField::Attribute("network_border_group"),
+        matching: FilterMatch::Exact,
+    },
+];
+
+pub struct AwsIntegration;
+
 impl AwsIntegration {
-    pub fn new(execution_id: Uuid) -> Self {
-        AwsIntegration { execution_id }
+    fn parse<T: DeserializeOwned>(&self, data: &str) -> Result<T, FetchError> {
+        Ok(serde_json::from_str(data)?)
+    }
+
+    fn normalize(&self, data: &AwsIpRanges) -> Vec<Result<NormalizedPrefix, PrefixError>> {
+        let ipv4_prefixes = data.prefixes.iter().map(|prefix| {
+            (
+                &prefix.ip_prefix,
+                &prefix.region,
+                &prefix.service,
+                &prefix.network_border_group,
+            )
+        });
+        let ipv6_prefixes = data.ipv6_prefixes.iter().map(|prefix| {
+            (
+                &prefix.ipv6_prefix,
+                &prefix.region,
+                &prefix.service,
+                &prefix.network_border_group,
+            )
+        });
+
+        ipv4_prefixes
+            .chain(ipv6_prefixes)
+            .map(|(ip_prefix, region, service, network_border_group)| {
+                NormalizedPrefix::new(self.name(), ip_prefix).map(|normalized| {
+                    normalized
+                        .with_region(region)
+                        .with_service(service)
+                        .with_attribute("network_border_group", network_border_group)
+                })
+            })
+            .collect()
     }
 }
 
 #[async_trait]
 impl Integration for AwsIntegration {
-    type DataModel = AwsIpRanges;
-
-    async fn update_cache(&mut self) -> IntegrationCache<Self::DataModel> {
-        let url = "https://ip-ranges.amazonaws.com/ip-ranges.json";
-        let response = match reqwest::get(url).await {
-            Ok(response) => response.text().await.ok(),
-            Err(err) => {
-                error!(
-					execution_id = %self.execution_id,
-					"Failed to fetch AWS data: {}", err);
-                return IntegrationCache::new(None);
-            }
-        };
+    fn name(&self) -> &'static str {
+        "aws"
+    }
 
-        let data = self.parse(response.as_ref().unwrap());
-        info!(
-            execution_id = %self.execution_id,
-            "AWS cache updated"
-        );
+    fn display_name(&self) -> &'static str {
+        "AWS"
+    }
 
-        IntegrationCache::new(data)
+    fn filter_schema(&self) -> FilterSchema {
+        // Without either flag only IPv4 prefixes are returned, as before IPv6 support was added
+        FilterSchema {
+            params: FILTERS,
+            family: FamilyPolicy::DefaultTo(&[Family::Ipv4]),
+        }
     }
 
-    fn parse(&self, data: &str) -> Option<Self::DataModel> {
-        match serde_json::from_str(data) {
-            Ok(parsed_data) => Some(parsed_data),
-            Err(err) => {
-                error!(
-					execution_id = %self.execution_id,
-					"Failed to parse JSON: {}", err);
-                None
-            }
+    async fn fetch(
+        &self,
+        client: &HttpClient,
+        execution_id: Uuid,
+    ) -> Result<FetchOutcome, FetchError> {
+        let url = config::upstream_url(self.name(), "url", AWS_URL, AWS_MIRROR_FILE);
+        let response = match client.get_if_modified(self.name(), &url).await? {
+            Some(response) => response,
+            None => return Ok(FetchOutcome::Unchanged),
+        };
+
+        // Skip the full parse when the published sync token is the one already cached
+        let token: AwsSyncToken = self.parse(&response)?;
+        if sync_token_unchanged(self.name(), token.sync_token.as_deref()) {
+            return Ok(FetchOutcome::Unchanged);
         }
+
+        let data = self.parse(&response)?;
+        info!(
+            execution_id = %execution_id,
+            "AWS data fetched"
+        );
+
+        Ok(FetchOutcome::Updated(FetchedData {
+            prefixes: self.normalize(&data),
+            version: Some(UpstreamVersion {
+                sync_token: data.sync_token.clone(),
+                created: data.create_date.clone(),
+            }),
+        }))
     }
 }
@@ -1,22 +1,32 @@
-use super::Integration;
-use crate::cache::IntegrationCache;
+use super::error::FetchError;
+use super::http::HttpClient;
+use super::normalized::{NormalizedPrefix, PrefixError};
+use super::{FetchOutcome, FetchedData, Integration, UpstreamVersion};
+use crate::config;
+use crate::filter::{FamilyPolicy, FilterField, FilterMatch, FilterParam, FilterSchema};
 use async_trait::async_trait;
-use reqwest;
 use rocket::serde::json::serde_json;
 use scraper::{Html, Selector};
 use serde::Deserialize;
-use tracing::{error, info};
+use tracing::info;
 use uuid::Uuid;
 
+const AZURE_DOWNLOAD_PAGE_URL: &str =
+    "https://www.microsoft.com/en-us/download/confirmation.aspx?id=56519";
+const AZURE_MIRROR_FILE: &str = "azure.json";
+
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AzureIpRanges {
+    pub change_number: Option<u64>,
     pub values: Vec<AzureValue>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AzureValue {
+    #[serde(default)]
+    pub name: String,
     pub properties: AzureProperties,
 }
 
@@ -28,79 +38,115 @@ pub struct AzureProperties {
     pub address_prefixes: Vec<String>,
 }
 
-pub struct AzureIntegration {
-    execution_id: Uuid,
-}
+const FILTERS: &[FilterParam] = &[
+    FilterParam {
+        name: "region",
+        field: FilterField::Region,
+        matching: FilterMatch::Exact,
+    },
+    FilterParam {
+        name: "system_service",
+        field: FilterField::Service,
+        matching: FilterMatch::Exact,
+    },
+];
 
-impl AzureIntegration {
-    pub fn new(execution_id: Uuid) -> Self {
-        AzureIntegration { execution_id }
-    }
+pub struct AzureIntegration;
 
-    async fn fetch_latest_url() -> Option<String> {
-        let url = "https://www.microsoft.com/en-us/download/confirmation.aspx?id=56519";
-        let response = reqwest::get(url).await.ok()?.text().await.ok()?;
+impl AzureIntegration {
+    async fn fetch_latest_url(&self, client: &HttpClient, url: &str) -> Result<String, FetchError> {
+        let response = client.get_page(url).await?;
 
         let document = Html::parse_document(&response);
-        let selector = Selector::parse("a").unwrap();
+        let selector = Selector::parse("a").map_err(|err| FetchError::Parse(err.to_string()))?;
 
         for element in document.select(&selector) {
             if let Some(href) = element.value().attr("href") {
                 if href.contains("ServiceTags_") {
-                    return Some(href.to_string());
+                    return Ok(href.to_string());
                 }
             }
         }
-        None
+        Err(FetchError::Schema(
+            "No service tags link found on the Azure download page".to_string(),
+        ))
+    }
+
+    fn parse(&self, data: &str) -> Result<AzureIpRanges, FetchError> {
+        Ok(serde_json::from_str(data)?)
+    }
+
+    fn normalize(&self, data: &AzureIpRanges) -> Vec<Result<NormalizedPrefix, PrefixError>> {
+        data.values
+            .iter()
+            .flat_map(|value| {
+                let properties = &value.properties;
+                properties.address_prefixes.iter().map(|prefix| {
+                    NormalizedPrefix::new(self.name(), prefix).map(|normalized| {
+                        normalized
+                            .with_region(&properties.region)
+                            .with_service(&properties.system_service)
+                            .with_attribute("service_tag", &value.name)
+                    })
+                })
+            })
+            .collect()
     }
 }
 
 #[async_trait]
 impl Integration for AzureIntegration {
-    type DataModel = AzureIpRanges;
+    fn name(&self) -> &'static str {
+        "azure"
+    }
 
-    async fn update_cache(&mut self) -> IntegrationCache<Self::DataModel> {
-        let url = match Self::fetch_latest_url().await {
+    fn display_name(&self) -> &'static str {
+        "Azure"
+    }
+
+    fn filter_schema(&self) -> FilterSchema {
+        FilterSchema {
+            params: FILTERS,
+            family: FamilyPolicy::Required,
+        }
+    }
+
+    async fn fetch(
+        &self,
+        client: &HttpClient,
+        execution_id: Uuid,
+    ) -> Result<FetchOutcome, FetchError> {
+        // A configured url or mirror directory points straight at the service tags file,
+        // otherwise the latest file is found through the download page
+        let url = match config::configured_url(self.name(), "url", AZURE_MIRROR_FILE) {
             Some(url) => url,
             None => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to find the latest Azure IP ranges URL"
-                );
-                return IntegrationCache::new(None);
+                let download_page_url = config::get()
+                    .provider_url(self.name(), "download_page_url")
+                    .unwrap_or(AZURE_DOWNLOAD_PAGE_URL);
+                self.fetch_latest_url(client, download_page_url).await?
             }
         };
 
-        let response = match reqwest::get(&url).await {
-            Ok(response) => response.text().await.ok(),
-            Err(err) => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to fetch Azure data: {}", err
-                );
-                return IntegrationCache::new(None);
-            }
+        let response = match client.get_if_modified(self.name(), &url).await? {
+            Some(response) => response,
+            None => return Ok(FetchOutcome::Unchanged),
         };
 
-        let data = self.parse(response.as_ref().unwrap());
+        let data = self.parse(&response)?;
         info!(
-            execution_id = %self.execution_id,
-            "Azure cache updated"
+            execution_id = %execution_id,
+            "Azure data fetched"
         );
 
-        IntegrationCache::new(data)
-    }
-
-    fn parse(&self, data: &str) -> Option<Self::DataModel> {
-        match serde_json::from_str(data) {
-            Ok(parsed_data) => Some(parsed_data),
-            Err(err) => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to parse JSON: {}", err
-                );
-                None
-            }
-        }
+        Ok(FetchOutcome::Updated(FetchedData {
+            prefixes: self.normalize(&data),
+            version: Some(UpstreamVersion {
+                sync_token: data
+                    .change_number
+                    .map(|change_number| change_number.to_string()),
+                created: None,
+            }),
+        }))
     }
 }
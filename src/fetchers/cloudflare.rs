@@ -1,13 +1,18 @@
-use super::Integration;
-use crate::cache::IntegrationCache;
+use super::error::FetchError;
+use super::http::HttpClient;
+use super::normalized::{NormalizedPrefix, PrefixError};
+use super::{FetchOutcome, FetchedData, Integration};
+use crate::config;
+use crate::filter::{FamilyPolicy, FilterSchema};
 use async_trait::async_trait;
-use reqwest;
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use tracing::info;
 use uuid::Uuid;
 
 const CLOUDFLARE_IPV4_URL: &str = "https://www.cloudflare.com/ips-v4/";
 const CLOUDFLARE_IPV6_URL: &str = "https://www.cloudflare.com/ips-v6/";
+const CLOUDFLARE_IPV4_MIRROR_FILE: &str = "cloudflare-ipv4.txt";
+const CLOUDFLARE_IPV6_MIRROR_FILE: &str = "cloudflare-ipv6.txt";
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CloudflareIpRanges {
@@ -15,67 +20,96 @@ pub struct CloudflareIpRanges {
     pub ipv6_cidrs: Vec<String>,
 }
 
-pub struct CloudflareIntegration {
-    execution_id: Uuid,
-}
+pub struct CloudflareIntegration;
 
 impl CloudflareIntegration {
-    pub fn new(execution_id: Uuid) -> Self {
-        CloudflareIntegration { execution_id }
+    fn parse_ip_ranges(response: &str) -> Vec<String> {
+        response
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect()
     }
 
-    async fn fetch_ip_ranges(url: &str) -> Option<Vec<String>> {
-        let response = reqwest::get(url).await.ok()?.text().await.ok()?;
-        let cidrs: Vec<String> = response
-            .lines()
-            .map(|line| line.trim().to_string())
-            .collect();
-        Some(cidrs)
+    fn normalize(&self, data: &CloudflareIpRanges) -> Vec<Result<NormalizedPrefix, PrefixError>> {
+        data.ipv4_cidrs
+            .iter()
+            .chain(&data.ipv6_cidrs)
+            .map(|prefix| NormalizedPrefix::new(self.name(), prefix))
+            .collect()
     }
 }
 
 #[async_trait]
 impl Integration for CloudflareIntegration {
-    type DataModel = CloudflareIpRanges;
+    fn name(&self) -> &'static str {
+        "cloudflare"
+    }
 
-    async fn update_cache(&mut self) -> IntegrationCache<Self::DataModel> {
-        let ipv4_cidrs = match Self::fetch_ip_ranges(CLOUDFLARE_IPV4_URL).await {
-            Some(cidrs) => cidrs,
-            None => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to fetch Cloudflare IPv4 data"
-                );
-                return IntegrationCache::new(None);
-            }
-        };
+    fn display_name(&self) -> &'static str {
+        "Cloudflare"
+    }
+
+    fn filter_schema(&self) -> FilterSchema {
+        FilterSchema {
+            params: &[],
+            family: FamilyPolicy::Required,
+        }
+    }
 
-        let ipv6_cidrs = match Self::fetch_ip_ranges(CLOUDFLARE_IPV6_URL).await {
-            Some(cidrs) => cidrs,
-            None => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to fetch Cloudflare IPv6 data"
-                );
-                return IntegrationCache::new(None);
-            }
+    async fn fetch(
+        &self,
+        client: &HttpClient,
+        execution_id: Uuid,
+    ) -> Result<FetchOutcome, FetchError> {
+        let ipv4_url = config::upstream_url(
+            self.name(),
+            "ipv4_url",
+            CLOUDFLARE_IPV4_URL,
+            CLOUDFLARE_IPV4_MIRROR_FILE,
+        );
+        let ipv6_url = config::upstream_url(
+            self.name(),
+            "ipv6_url",
+            CLOUDFLARE_IPV6_URL,
+            CLOUDFLARE_IPV6_MIRROR_FILE,
+        );
+
+        let ipv4_response = client.get_if_modified(self.name(), &ipv4_url).await?;
+        let ipv6_response = client.get_if_modified(self.name(), &ipv6_url).await?;
+
+        // Both lists are needed to rebuild the cache, so an unchanged list is fetched again
+        // when only the other one changed
+        let (ipv4_response, ipv6_response) = match (ipv4_response, ipv6_response) {
+            (None, None) => return Ok(FetchOutcome::Unchanged),
+            (Some(ipv4_response), Some(ipv6_response)) => (ipv4_response, ipv6_response),
+            (Some(ipv4_response), None) => (
+                ipv4_response,
+                client.get_text(self.name(), &ipv6_url).await?,
+            ),
+            (None, Some(ipv6_response)) => (
+                client.get_text(self.name(), &ipv4_url).await?,
+                ipv6_response,
+            ),
         };
 
+        let ipv4_cidrs = Self::parse_ip_ranges(&ipv4_response);
+        let ipv6_cidrs = Self::parse_ip_ranges(&ipv6_response);
+
         let data = CloudflareIpRanges {
             ipv4_cidrs,
             ipv6_cidrs,
         };
 
         info!(
-            execution_id = %self.execution_id,
-            "Cloudflare cache updated"
+            execution_id = %execution_id,
+            "Cloudflare data fetched"
         );
 
-        IntegrationCache::new(Some(data))
-    }
-
-    fn parse(&self, _data: &str) -> Option<Self::DataModel> {
-        // Cloudflare data is directly fetched and parsed, so this is not used
-        None
+        Ok(FetchOutcome::Updated(FetchedData {
+            prefixes: self.normalize(&data),
+            version: None,
+        }))
     }
 }
@@ -1,15 +1,23 @@
-use super::Integration;
-use crate::cache::IntegrationCache;
+use super::error::FetchError;
+use super::http::HttpClient;
+use super::normalized::{NormalizedPrefix, PrefixError};
+use super::{FetchOutcome, FetchedData, Integration};
+use crate::config;
+use crate::filter::{FamilyPolicy, FilterField, FilterMatch, FilterParam, FilterSchema};
 use async_trait::async_trait;
-use reqwest;
-use rocket::serde::json::serde_json;
 use serde::Deserialize;
-use tracing::{error, info};
+use tracing::info;
 use uuid::Uuid;
 
+const DIGITALOCEAN_URL: &str = "https://digitalocean.com/geo/google.csv";
+const DIGITALOCEAN_MIRROR_FILE: &str = "digitalocean.csv";
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct DigitalOceanIpRanges {
     pub ranges: Vec<DigitalOceanRange>,
+    // Rows without the prefix, country and region fields
+    #[serde(skip)]
+    pub malformed_rows: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -19,21 +27,32 @@ pub struct DigitalOceanRange {
     pub region: String,
 }
 
-pub struct DigitalOceanIntegration {
-    execution_id: Uuid,
-}
+const FILTERS: &[FilterParam] = &[
+    FilterParam {
+        name: "alpha2code",
+        field: FilterField::Country,
+        matching: FilterMatch::Exact,
+    },
+    FilterParam {
+        name: "region",
+        field: FilterField::Region,
+        matching: FilterMatch::Contains,
+    },
+];
 
-impl DigitalOceanIntegration {
-    pub fn new(execution_id: Uuid) -> Self {
-        DigitalOceanIntegration { execution_id }
-    }
+pub struct DigitalOceanIntegration;
 
-    async fn fetch_ip_ranges() -> Option<Vec<DigitalOceanRange>> {
-        let url = "https://digitalocean.com/geo/google.csv";
-        let response = reqwest::get(url).await.ok()?.text().await.ok()?;
+impl DigitalOceanIntegration {
+    fn parse_ip_ranges(response: &str) -> DigitalOceanIpRanges {
         let mut ip_ranges = Vec::new();
+        let mut malformed_rows = Vec::new();
 
-        for line in response.lines() {
+        // Skip blank lines and the comment header
+        for line in response
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        {
             let fields: Vec<&str> = line.split(',').collect();
             if fields.len() >= 3 {
                 let ip_prefix = fields[0].trim().to_owned();
@@ -45,48 +64,78 @@ impl DigitalOceanIntegration {
                     alpha2code,
                     region,
                 });
+            } else {
+                malformed_rows.push(line.to_string());
             }
         }
 
-        Some(ip_ranges)
+        DigitalOceanIpRanges {
+            ranges: ip_ranges,
+            malformed_rows,
+        }
+    }
+
+    fn normalize(&self, data: &DigitalOceanIpRanges) -> Vec<Result<NormalizedPrefix, PrefixError>> {
+        data.ranges
+            .iter()
+            .map(|range| {
+                NormalizedPrefix::new(self.name(), &range.ip_prefix).map(|normalized| {
+                    normalized
+                        .with_region(&range.region)
+                        .with_country(&range.alpha2code)
+                })
+            })
+            .chain(
+                data.malformed_rows
+                    .iter()
+                    .map(|row| Err(PrefixError::Malformed(row.clone()))),
+            )
+            .collect()
     }
 }
 
 #[async_trait]
 impl Integration for DigitalOceanIntegration {
-    type DataModel = DigitalOceanIpRanges;
-
-    async fn update_cache(&mut self) -> IntegrationCache<Self::DataModel> {
-        let ip_ranges = match Self::fetch_ip_ranges().await {
-            Some(ranges) => ranges,
-            None => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to fetch DigitalOcean IP ranges"
-                );
-                return IntegrationCache::new(None);
-            }
+    fn name(&self) -> &'static str {
+        "digitalocean"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "DigitalOcean"
+    }
+
+    fn filter_schema(&self) -> FilterSchema {
+        FilterSchema {
+            params: FILTERS,
+            family: FamilyPolicy::Required,
+        }
+    }
+
+    async fn fetch(
+        &self,
+        client: &HttpClient,
+        execution_id: Uuid,
+    ) -> Result<FetchOutcome, FetchError> {
+        let url = config::upstream_url(
+            self.name(),
+            "url",
+            DIGITALOCEAN_URL,
+            DIGITALOCEAN_MIRROR_FILE,
+        );
+        let response = match client.get_if_modified(self.name(), &url).await? {
+            Some(response) => response,
+            None => return Ok(FetchOutcome::Unchanged),
         };
 
-        let data_model = DigitalOceanIpRanges { ranges: ip_ranges };
+        let data_model = Self::parse_ip_ranges(&response);
         info!(
-            execution_id = %self.execution_id,
-            "DigitalOcean cache updated"
+            execution_id = %execution_id,
+            "DigitalOcean data fetched"
         );
 
-        IntegrationCache::new(Some(data_model))
-    }
-
-    fn parse(&self, data: &str) -> Option<Self::DataModel> {
-        match serde_json::from_str(data) {
-            Ok(parsed_data) => Some(parsed_data),
-            Err(err) => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to parse DigitalOcean JSON: {}", err
-                );
-                None
-            }
-        }
+        Ok(FetchOutcome::Updated(FetchedData {
+            prefixes: self.normalize(&data_model),
+            version: None,
+        }))
     }
 }
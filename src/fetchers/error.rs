@@ -0,0 +1,65 @@
+use rocket::serde::json::serde_json;
+use std::fmt;
+
+// Why fetching an integration's upstream data failed
+#[derive(Debug, Clone)]
+pub enum FetchError {
+    // Connection failures, timeouts and interrupted bodies
+    Network(String),
+    // The upstream answered with an unexpected status
+    HttpStatus { url: String, status: u16 },
+    BodyTooLarge { limit: u64 },
+    // A local source could not be read
+    File(String),
+    // The document is not valid JSON, CSV or HTML
+    Parse(String),
+    // The document parsed but does not have the expected shape
+    Schema(String),
+    // The data parsed but was rejected as implausible
+    SanityCheck(String),
+}
+
+impl FetchError {
+    // Short name of the error kind, reported in the status endpoint
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FetchError::Network(_) => "network",
+            FetchError::HttpStatus { .. } => "http_status",
+            FetchError::BodyTooLarge { .. } => "body_too_large",
+            FetchError::File(_) => "file",
+            FetchError::Parse(_) => "parse",
+            FetchError::Schema(_) => "schema",
+            FetchError::SanityCheck(_) => "sanity_check",
+        }
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Network(err) => write!(f, "Network error: {}", err),
+            FetchError::HttpStatus { url, status } => write!(f, "{} returned HTTP {}", url, status),
+            FetchError::BodyTooLarge { limit } => write!(f, "Response exceeds {} bytes", limit),
+            FetchError::File(err) => write!(f, "Failed to read file {}", err),
+            FetchError::Parse(err) => write!(f, "Failed to parse response: {}", err),
+            FetchError::Schema(err) => write!(f, "Unexpected response format: {}", err),
+            FetchError::SanityCheck(err) => write!(f, "Sanity check failed: {}", err),
+        }
+    }
+}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(err: reqwest::Error) -> Self {
+        FetchError::Network(err.to_string())
+    }
+}
+
+// Syntax errors mean the body is not JSON at all, data errors that fields are missing or mistyped
+impl From<serde_json::Error> for FetchError {
+    fn from(err: serde_json::Error) -> Self {
+        match err.classify() {
+            serde_json::error::Category::Data => FetchError::Schema(err.to_string()),
+            _ => FetchError::Parse(err.to_string()),
+        }
+    }
+}
@@ -1,12 +1,18 @@
-use super::Integration;
-use crate::cache::IntegrationCache;
+use super::error::FetchError;
+use super::http::HttpClient;
+use super::normalized::{NormalizedPrefix, PrefixError};
+use super::{FetchOutcome, FetchedData, Integration};
+use crate::config;
+use crate::filter::{FamilyPolicy, FilterSchema};
 use async_trait::async_trait;
-use reqwest;
 use rocket::serde::json::serde_json;
 use serde::Deserialize;
-use tracing::{error, info};
+use tracing::info;
 use uuid::Uuid;
 
+const FASTLY_URL: &str = "https://api.fastly.com/public-ip-list";
+const FASTLY_MIRROR_FILE: &str = "fastly.json";
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct FastlyIpRanges {
     #[serde(rename = "addresses")]
@@ -14,50 +20,59 @@ pub struct FastlyIpRanges {
     pub ipv6_addresses: Vec<String>,
 }
 
-pub struct FastlyIntegration {
-    execution_id: Uuid,
-}
+pub struct FastlyIntegration;
 
 impl FastlyIntegration {
-    pub fn new(execution_id: Uuid) -> Self {
-        FastlyIntegration { execution_id }
+    fn parse(&self, data: &str) -> Result<FastlyIpRanges, FetchError> {
+        Ok(serde_json::from_str(data)?)
+    }
+
+    fn normalize(&self, data: &FastlyIpRanges) -> Vec<Result<NormalizedPrefix, PrefixError>> {
+        data.ipv4_addresses
+            .iter()
+            .chain(&data.ipv6_addresses)
+            .map(|prefix| NormalizedPrefix::new(self.name(), prefix))
+            .collect()
     }
 }
 
 #[async_trait]
 impl Integration for FastlyIntegration {
-    type DataModel = FastlyIpRanges;
-
-    async fn update_cache(&mut self) -> IntegrationCache<Self::DataModel> {
-        let url = "https://api.fastly.com/public-ip-list";
-        let response = match reqwest::get(url).await {
-            Ok(response) => response.text().await.ok(),
-            Err(err) => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to fetch Fastly data: {}", err);
-                return IntegrationCache::new(None);
-            }
+    fn name(&self) -> &'static str {
+        "fastly"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Fastly"
+    }
+
+    fn filter_schema(&self) -> FilterSchema {
+        FilterSchema {
+            params: &[],
+            family: FamilyPolicy::Required,
+        }
+    }
+
+    async fn fetch(
+        &self,
+        client: &HttpClient,
+        execution_id: Uuid,
+    ) -> Result<FetchOutcome, FetchError> {
+        let url = config::upstream_url(self.name(), "url", FASTLY_URL, FASTLY_MIRROR_FILE);
+        let response = match client.get_if_modified(self.name(), &url).await? {
+            Some(response) => response,
+            None => return Ok(FetchOutcome::Unchanged),
         };
 
-        let data = self.parse(response.as_ref().unwrap());
+        let data = self.parse(&response)?;
         info!(
-            execution_id = %self.execution_id,
-            "Fastly cache updated"
+            execution_id = %execution_id,
+            "Fastly data fetched"
         );
 
-        IntegrationCache::new(data)
-    }
-
-    fn parse(&self, data: &str) -> Option<Self::DataModel> {
-        match serde_json::from_str(data) {
-            Ok(parsed_data) => Some(parsed_data),
-            Err(err) => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to parse JSON: {}", err);
-                None
-            }
-        }
+        Ok(FetchOutcome::Updated(FetchedData {
+            prefixes: self.normalize(&data),
+            version: None,
+        }))
     }
 }
@@ -1,14 +1,32 @@
-use super::Integration;
-use crate::cache::IntegrationCache;
+use super::error::FetchError;
+use super::http::HttpClient;
+use super::normalized::{NormalizedPrefix, PrefixError};
+use super::{sync_token_unchanged, FetchOutcome, FetchedData, Integration, UpstreamVersion};
+use crate::config;
+use crate::filter::{FamilyPolicy, FilterField, FilterMatch, FilterParam, FilterSchema};
 use async_trait::async_trait;
-use reqwest;
 use rocket::serde::json::serde_json;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
-use tracing::{error, info};
+use tracing::info;
 use uuid::Uuid;
 
+const GCP_URL: &str = "https://www.gstatic.com/ipranges/cloud.json";
+const GCP_MIRROR_FILE: &str = "gcp.json";
+
+// Only the sync token of the document, read before deciding on a full parse
+#[derive(Debug, Deserialize)]
+struct GcpSyncToken {
+    #[serde(rename = "syncToken")]
+    sync_token: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct GcpIpRanges {
+    #[serde(rename = "syncToken")]
+    pub sync_token: Option<String>,
+    #[serde(rename = "creationTime")]
+    pub creation_time: Option<String>,
     pub prefixes: Vec<GcpPrefix>,
 }
 
@@ -22,52 +40,91 @@ pub struct GcpPrefix {
     pub scope: String,
 }
 
-pub struct GcpIntegration {
-    execution_id: Uuid,
-}
+const FILTERS: &[FilterParam] = &[
+    FilterParam {
+        name: "scope",
+        field: FilterField::Region,
+        matching: FilterMatch::Exact,
+    },
+    FilterParam {
+        name: "service",
+        field: FilterField::Service,
+        matching: FilterMatch::Exact,
+    },
+];
+
+pub struct GcpIntegration;
 
 impl GcpIntegration {
-    pub fn new(execution_id: Uuid) -> Self {
-        GcpIntegration { execution_id }
+    fn parse<T: DeserializeOwned>(&self, data: &str) -> Result<T, FetchError> {
+        Ok(serde_json::from_str(data)?)
+    }
+
+    fn normalize(&self, data: &GcpIpRanges) -> Vec<Result<NormalizedPrefix, PrefixError>> {
+        data.prefixes
+            .iter()
+            .map(|prefix| {
+                let ip_prefix = prefix
+                    .ipv4_prefix
+                    .as_deref()
+                    .or(prefix.ipv6_prefix.as_deref())
+                    .unwrap_or_default();
+                NormalizedPrefix::new(self.name(), ip_prefix).map(|normalized| {
+                    normalized
+                        .with_region(&prefix.scope)
+                        .with_service(&prefix.service)
+                })
+            })
+            .collect()
     }
 }
 
 #[async_trait]
 impl Integration for GcpIntegration {
-    type DataModel = GcpIpRanges;
+    fn name(&self) -> &'static str {
+        "gcp"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "GCP"
+    }
 
-    async fn update_cache(&mut self) -> IntegrationCache<Self::DataModel> {
-        let url = "https://www.gstatic.com/ipranges/cloud.json";
-        let response = match reqwest::get(url).await {
-            Ok(response) => response.text().await.ok(),
-            Err(err) => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to fetch GCP data: {}", err
-                );
-                return IntegrationCache::new(None);
-            }
+    fn filter_schema(&self) -> FilterSchema {
+        FilterSchema {
+            params: FILTERS,
+            family: FamilyPolicy::Required,
+        }
+    }
+
+    async fn fetch(
+        &self,
+        client: &HttpClient,
+        execution_id: Uuid,
+    ) -> Result<FetchOutcome, FetchError> {
+        let url = config::upstream_url(self.name(), "url", GCP_URL, GCP_MIRROR_FILE);
+        let response = match client.get_if_modified(self.name(), &url).await? {
+            Some(response) => response,
+            None => return Ok(FetchOutcome::Unchanged),
         };
 
-        let data = self.parse(response.as_ref().unwrap());
+        // Skip the full parse when the published sync token is the one already cached
+        let token: GcpSyncToken = self.parse(&response)?;
+        if sync_token_unchanged(self.name(), token.sync_token.as_deref()) {
+            return Ok(FetchOutcome::Unchanged);
+        }
+
+        let data = self.parse(&response)?;
         info!(
-            execution_id = %self.execution_id,
-            "GCP cache updated"
+            execution_id = %execution_id,
+            "GCP data fetched"
         );
 
-        IntegrationCache::new(data)
-    }
-
-    fn parse(&self, data: &str) -> Option<Self::DataModel> {
-        match serde_json::from_str(data) {
-            Ok(parsed_data) => Some(parsed_data),
-            Err(err) => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to parse JSON: {}", err
-                );
-                None
-            }
-        }
+        Ok(FetchOutcome::Updated(FetchedData {
+            prefixes: self.normalize(&data),
+            version: Some(UpstreamVersion {
+                sync_token: data.sync_token.clone(),
+                created: data.creation_time.clone(),
+            }),
+        }))
     }
 }
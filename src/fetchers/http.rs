@@ -0,0 +1,320 @@
+use super::error::FetchError;
+use super::sanity;
+use crate::cache;
+use crate::config::HttpConfig;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Certificate, Client, NoProxy, Proxy, Response, StatusCode, Url};
+use rocket::tokio::{fs, time};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::warn;
+
+// Cache validators returned by an upstream source
+#[derive(Debug, Clone, Default)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+lazy_static! {
+    // Validators of the last response of every source, keyed by integration and URL
+    static ref VALIDATORS: DashMap<(String, String), Validators> = DashMap::new();
+}
+
+// HTTP client shared by every integration, built once from the [http] config
+pub struct HttpClient {
+    client: Client,
+    retries: u32,
+    retry_delay: Duration,
+    max_response_bytes: u64,
+}
+
+impl HttpClient {
+    pub fn from_config(config: &HttpConfig) -> Result<Self, String> {
+        let mut builder = Client::builder()
+            .user_agent(config.user_agent.as_str())
+            .connect_timeout(Duration::from_secs(config.connect_timeout))
+            .read_timeout(Duration::from_secs(config.read_timeout));
+
+        // Without an explicit proxy, reqwest follows HTTPS_PROXY, HTTP_PROXY and NO_PROXY
+        if let Some(proxy) = &config.proxy {
+            let proxy = Proxy::all(proxy)
+                .map_err(|err| format!("Invalid proxy {}: {}", proxy, err))?
+                .no_proxy(config.no_proxy.as_deref().and_then(NoProxy::from_string));
+            builder = builder.proxy(proxy);
+        }
+
+        for path in &config.root_certificates {
+            let pem = std::fs::read(path)
+                .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+            let certificate = Certificate::from_pem(&pem)
+                .map_err(|err| format!("Invalid certificate {}: {}", path.display(), err))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        Ok(HttpClient {
+            client: builder
+                .build()
+                .map_err(|err| format!("Failed to build HTTP client: {}", err))?,
+            retries: config.retries,
+            retry_delay: Duration::from_millis(config.retry_delay),
+            max_response_bytes: config.max_response_bytes,
+        })
+    }
+
+    // GET a source unconditionally, remembering its validators for later conditional requests
+    pub async fn get_text(&self, integration_name: &str, url: &str) -> Result<String, FetchError> {
+        self.get(integration_name, url, false)
+            .await
+            .map(|body| body.unwrap_or_default())
+    }
+
+    // GET a source with the validators of its last response, returning None on 304 Not Modified.
+    // Validators are only sent while the integration has cached data to fall back on.
+    pub async fn get_if_modified(
+        &self,
+        integration_name: &str,
+        url: &str,
+    ) -> Result<Option<String>, FetchError> {
+        self.get(
+            integration_name,
+            url,
+            cache::get(integration_name).is_some(),
+        )
+        .await
+    }
+
+    // GET an HTML page, such as a download page linking to the data document
+    pub async fn get_page(&self, url: &str) -> Result<String, FetchError> {
+        if let Some(path) = local_path(url) {
+            return self.read_file(&path).await;
+        }
+
+        let response = self.send(url, None).await?;
+        if !response.status().is_success() {
+            return Err(FetchError::HttpStatus {
+                url: url.to_string(),
+                status: response.status().as_u16(),
+            });
+        }
+
+        self.read_body(response).await
+    }
+
+    // POST a JSON payload once, leaving retries to the caller
+    pub async fn post_json(
+        &self,
+        url: &str,
+        headers: &[(&str, String)],
+        body: Vec<u8>,
+    ) -> Result<(), FetchError> {
+        let mut request = self
+            .client
+            .post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body);
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(FetchError::HttpStatus {
+                url: url.to_string(),
+                status: response.status().as_u16(),
+            });
+        }
+        Ok(())
+    }
+
+    async fn get(
+        &self,
+        integration_name: &str,
+        url: &str,
+        conditional: bool,
+    ) -> Result<Option<String>, FetchError> {
+        let key = (integration_name.to_string(), url.to_string());
+        let previous = if conditional {
+            VALIDATORS.get(&key).map(|entry| entry.value().clone())
+        } else {
+            None
+        };
+
+        let fetched = match local_path(url) {
+            Some(path) => self.get_file(&path, previous.as_ref()).await?,
+            None => self.get_url(url, previous.as_ref()).await?,
+        };
+        let (body, validators) = match fetched {
+            Some(fetched) => fetched,
+            None => return Ok(None),
+        };
+        sanity::check_document(&body)?;
+
+        // Only remember the validators of documents that were accepted
+        VALIDATORS.insert(key, validators);
+        Ok(Some(body))
+    }
+
+    async fn get_url(
+        &self,
+        url: &str,
+        previous: Option<&Validators>,
+    ) -> Result<Option<(String, Validators)>, FetchError> {
+        let response = self.send(url, previous).await?;
+        if previous.is_some() && response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(FetchError::HttpStatus {
+                url: url.to_string(),
+                status: response.status().as_u16(),
+            });
+        }
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let validators = Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+
+        let body = self.read_body(response).await?;
+        Ok(Some((body, validators)))
+    }
+
+    // Read a local source, treating a file with the same size and modification time as unchanged
+    async fn get_file(
+        &self,
+        path: &Path,
+        previous: Option<&Validators>,
+    ) -> Result<Option<(String, Validators)>, FetchError> {
+        let metadata = fs::metadata(path)
+            .await
+            .map_err(|err| file_error(path, err))?;
+        let modified = metadata.modified().map_err(|err| file_error(path, err))?;
+        let validators = Validators {
+            etag: Some(metadata.len().to_string()),
+            last_modified: Some(DateTime::<Utc>::from(modified).to_rfc3339()),
+        };
+        if previous.is_some_and(|previous| {
+            previous.etag == validators.etag && previous.last_modified == validators.last_modified
+        }) {
+            return Ok(None);
+        }
+
+        let body = self.read_file(path).await?;
+        Ok(Some((body, validators)))
+    }
+
+    async fn read_file(&self, path: &Path) -> Result<String, FetchError> {
+        let body = fs::read(path).await.map_err(|err| file_error(path, err))?;
+        if body.len() as u64 > self.max_response_bytes {
+            return Err(FetchError::BodyTooLarge {
+                limit: self.max_response_bytes,
+            });
+        }
+
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    // Send a request, retrying connection failures and transient statuses with a doubling delay
+    async fn send(
+        &self,
+        url: &str,
+        validators: Option<&Validators>,
+    ) -> Result<Response, FetchError> {
+        let mut attempt = 0;
+        loop {
+            let mut request = self.client.get(url);
+            if let Some(validators) = validators {
+                if let Some(etag) = &validators.etag {
+                    request = request.header(IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = &validators.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+
+            let error = match request.send().await {
+                Ok(response) if !is_transient(response.status()) => return Ok(response),
+                Ok(response) => FetchError::HttpStatus {
+                    url: url.to_string(),
+                    status: response.status().as_u16(),
+                },
+                Err(err) => FetchError::from(err),
+            };
+
+            if attempt >= self.retries {
+                return Err(error);
+            }
+
+            let delay = self.retry_delay.saturating_mul(1 << attempt.min(16));
+            attempt += 1;
+            warn!(
+                url = url,
+                attempt = attempt,
+                "Retrying request in {}ms: {}",
+                delay.as_millis(),
+                error
+            );
+            time::sleep(delay).await;
+        }
+    }
+
+    // Read the response body, giving up once it exceeds the configured size
+    async fn read_body(&self, mut response: Response) -> Result<String, FetchError> {
+        let too_large = || FetchError::BodyTooLarge {
+            limit: self.max_response_bytes,
+        };
+        if response
+            .content_length()
+            .is_some_and(|length| length > self.max_response_bytes)
+        {
+            return Err(too_large());
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if (body.len() + chunk.len()) as u64 > self.max_response_bytes {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+}
+
+// Drop the validators of an integration whose fetch failed, so a 304 can't hide the failure
+pub fn clear_validators(integration_name: &str) {
+    VALIDATORS.retain(|(name, _), _| name != integration_name);
+}
+
+// Path of a source given as a file:// URL or a plain path, e.g. in a mirror directory
+fn local_path(url: &str) -> Option<PathBuf> {
+    if url.starts_with("file://") {
+        return Url::parse(url).ok()?.to_file_path().ok();
+    }
+    if url.contains("://") {
+        return None;
+    }
+    Some(PathBuf::from(url))
+}
+
+fn file_error(path: &Path, err: io::Error) -> FetchError {
+    FetchError::File(format!("{}: {}", path.display(), err))
+}
+
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
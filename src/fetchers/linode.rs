@@ -1,15 +1,23 @@
-use super::Integration;
-use crate::cache::IntegrationCache;
+use super::error::FetchError;
+use super::http::HttpClient;
+use super::normalized::{NormalizedPrefix, PrefixError};
+use super::{FetchOutcome, FetchedData, Integration};
+use crate::config;
+use crate::filter::{FamilyPolicy, FilterField, FilterMatch, FilterParam, FilterSchema};
 use async_trait::async_trait;
-use reqwest;
-use rocket::serde::json::serde_json;
 use serde::Deserialize;
-use tracing::{error, info};
+use tracing::info;
 use uuid::Uuid;
 
+const LINODE_URL: &str = "https://geoip.linode.com/";
+const LINODE_MIRROR_FILE: &str = "linode.csv";
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct LinodeIpRanges {
     pub ranges: Vec<LinodeRange>,
+    // Rows without the prefix, country and region fields
+    #[serde(skip)]
+    pub malformed_rows: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -19,21 +27,32 @@ pub struct LinodeRange {
     pub region: String,
 }
 
-pub struct LinodeIntegration {
-    execution_id: Uuid,
-}
+const FILTERS: &[FilterParam] = &[
+    FilterParam {
+        name: "alpha2code",
+        field: FilterField::Country,
+        matching: FilterMatch::Exact,
+    },
+    FilterParam {
+        name: "region",
+        field: FilterField::Region,
+        matching: FilterMatch::Contains,
+    },
+];
 
-impl LinodeIntegration {
-    pub fn new(execution_id: Uuid) -> Self {
-        LinodeIntegration { execution_id }
-    }
+pub struct LinodeIntegration;
 
-    async fn fetch_ip_ranges() -> Option<Vec<LinodeRange>> {
-        let url = "https://geoip.linode.com/";
-        let response = reqwest::get(url).await.ok()?.text().await.ok()?;
+impl LinodeIntegration {
+    fn parse_ip_ranges(response: &str) -> LinodeIpRanges {
         let mut ip_ranges = Vec::new();
+        let mut malformed_rows = Vec::new();
 
-        for line in response.lines().skip(3) {
+        // Skip blank lines and the comment header
+        for line in response
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        {
             let fields: Vec<&str> = line.split(',').collect();
             if fields.len() >= 3 {
                 let ip_prefix = fields[0].trim();
@@ -45,48 +64,73 @@ impl LinodeIntegration {
                     alpha2code,
                     region,
                 });
+            } else {
+                malformed_rows.push(line.to_string());
             }
         }
 
-        Some(ip_ranges)
+        LinodeIpRanges {
+            ranges: ip_ranges,
+            malformed_rows,
+        }
+    }
+
+    fn normalize(&self, data: &LinodeIpRanges) -> Vec<Result<NormalizedPrefix, PrefixError>> {
+        data.ranges
+            .iter()
+            .map(|range| {
+                NormalizedPrefix::new(self.name(), &range.ip_prefix).map(|normalized| {
+                    normalized
+                        .with_region(&range.region)
+                        .with_country(&range.alpha2code)
+                })
+            })
+            .chain(
+                data.malformed_rows
+                    .iter()
+                    .map(|row| Err(PrefixError::Malformed(row.clone()))),
+            )
+            .collect()
     }
 }
 
 #[async_trait]
 impl Integration for LinodeIntegration {
-    type DataModel = LinodeIpRanges;
-
-    async fn update_cache(&mut self) -> IntegrationCache<Self::DataModel> {
-        let ip_ranges = match Self::fetch_ip_ranges().await {
-            Some(ranges) => ranges,
-            None => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to fetch Linode IP ranges"
-                );
-                return IntegrationCache::new(None);
-            }
+    fn name(&self) -> &'static str {
+        "linode"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Linode"
+    }
+
+    fn filter_schema(&self) -> FilterSchema {
+        FilterSchema {
+            params: FILTERS,
+            family: FamilyPolicy::Required,
+        }
+    }
+
+    async fn fetch(
+        &self,
+        client: &HttpClient,
+        execution_id: Uuid,
+    ) -> Result<FetchOutcome, FetchError> {
+        let url = config::upstream_url(self.name(), "url", LINODE_URL, LINODE_MIRROR_FILE);
+        let response = match client.get_if_modified(self.name(), &url).await? {
+            Some(response) => response,
+            None => return Ok(FetchOutcome::Unchanged),
         };
 
-        let data_model = LinodeIpRanges { ranges: ip_ranges };
+        let data_model = Self::parse_ip_ranges(&response);
         info!(
-            execution_id = %self.execution_id,
-            "Linode cache updated"
+            execution_id = %execution_id,
+            "Linode data fetched"
         );
 
-        IntegrationCache::new(Some(data_model))
-    }
-
-    fn parse(&self, data: &str) -> Option<Self::DataModel> {
-        match serde_json::from_str(data) {
-            Ok(parsed_data) => Some(parsed_data),
-            Err(err) => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to parse Linode JSON: {}", err
-                );
-                None
-            }
-        }
+        Ok(FetchOutcome::Updated(FetchedData {
+            prefixes: self.normalize(&data_model),
+            version: None,
+        }))
     }
 }
@@ -2,232 +2,258 @@ pub mod aws;
 pub mod azure;
 pub mod cloudflare;
 pub mod digitalocean;
+pub mod error;
 pub mod fastly;
 pub mod gcp;
+pub mod http;
 pub mod linode;
+pub mod normalized;
 pub mod oracle;
+pub mod sanity;
 
-use crate::cache::IntegrationCache;
+use crate::cache::{self, IntegrationCache};
+use crate::config;
+use crate::filter::FilterSchema;
 use async_trait::async_trait;
-use std::collections::HashMap;
-use tracing::{error, info};
+use lazy_static::lazy_static;
+use rand::Rng;
+use rocket::tokio::{task, time};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
-use aws::AwsIpRanges;
-use azure::AzureIpRanges;
-use cloudflare::CloudflareIpRanges;
-use digitalocean::DigitalOceanIpRanges;
-use fastly::FastlyIpRanges;
-use gcp::GcpIpRanges;
-use linode::LinodeIpRanges;
-use oracle::OracleIpRanges;
-
-pub enum IntegrationResult {
-    Aws(IntegrationCache<AwsIpRanges>),
-    Azure(IntegrationCache<AzureIpRanges>),
-    Cloudflare(IntegrationCache<CloudflareIpRanges>),
-    DigitalOcean(IntegrationCache<DigitalOceanIpRanges>),
-    Fastly(IntegrationCache<FastlyIpRanges>),
-    Gcp(IntegrationCache<GcpIpRanges>),
-    Linode(IntegrationCache<LinodeIpRanges>),
-    Oracle(IntegrationCache<OracleIpRanges>),
+use error::FetchError;
+use http::HttpClient;
+use normalized::{NormalizedPrefix, PrefixError};
+
+// How often an integration's upstream is polled, and how failed fetches are retried
+#[derive(Debug, Clone, Copy)]
+pub struct RefreshPolicy {
+    pub interval: Duration,
+    // Fraction of the interval by which each refresh is randomly moved, so providers drift apart
+    pub jitter: f64,
+    // First retry delay after a failure, doubled on every consecutive failure
+    pub backoff_min: Duration,
+    // Longest retry delay, never longer than the interval itself
+    pub backoff_max: Duration,
 }
 
-#[async_trait]
-pub trait Integration {
-    type DataModel;
+impl Default for RefreshPolicy {
+    fn default() -> Self {
+        RefreshPolicy {
+            interval: config::get().refresh_interval(),
+            jitter: 0.1,
+            backoff_min: Duration::from_secs(30),
+            backoff_max: Duration::from_secs(600),
+        }
+    }
+}
+
+impl RefreshPolicy {
+    // Delay until the next refresh after a successful fetch
+    pub fn next_refresh(&self) -> Duration {
+        let spread = rand::thread_rng().gen_range(-self.jitter..=self.jitter);
+        self.interval.mul_f64(1.0 + spread)
+    }
 
-    async fn update_cache(&mut self) -> IntegrationCache<Self::DataModel>;
-    fn parse(&self, data: &str) -> Option<Self::DataModel>;
+    // Delay until the next attempt after the given number of consecutive failures
+    pub fn backoff(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(16);
+        self.backoff_min
+            .saturating_mul(1 << exponent)
+            .min(self.backoff_max)
+            .min(self.interval)
+    }
 }
 
-// Update the update_all function to include Oracle and Cloudflare tasks
-pub async fn update_all(execution_id: Uuid) -> HashMap<String, IntegrationResult> {
-    let mut all_data = HashMap::new();
-
-    info!(execution_id = %execution_id, "Starting update for all integrations");
-
-    // AWS integration update task
-    let aws_task = async {
-        info!(execution_id = %execution_id, "Starting AWS integration update");
-        let mut aws_integration = aws::AwsIntegration::new(execution_id);
-        let aws_cache = aws_integration.update_cache().await;
-        if let Some(_aws_data) = &aws_cache.data {
-            info!(execution_id = %execution_id, "AWS integration update succeeded");
-            Some(("aws".to_string(), IntegrationResult::Aws(aws_cache)))
-        } else {
-            error!(execution_id = %execution_id, "AWS integration update failed");
-            None
-        }
-    };
+// Version marker published by the upstream alongside its ranges
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpstreamVersion {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+}
 
-    // Azure integration update task
-    let azure_task = async {
-        info!(execution_id = %execution_id, "Starting Azure integration update");
-        let mut azure_integration = azure::AzureIntegration::new(execution_id);
-        let azure_cache = azure_integration.update_cache().await;
-        if let Some(_azure_data) = &azure_cache.data {
-            info!(execution_id = %execution_id, "Azure integration update succeeded");
-            Some(("azure".to_string(), IntegrationResult::Azure(azure_cache)))
-        } else {
-            error!(execution_id = %execution_id, "Azure integration update failed");
-            None
-        }
-    };
+// Normalized upstream data returned by a successful fetch
+pub struct FetchedData {
+    pub prefixes: Vec<Result<NormalizedPrefix, PrefixError>>,
+    pub version: Option<UpstreamVersion>,
+}
 
-    // Cloudflare integration update task
-    let cloudflare_task = async {
-        info!(execution_id = %execution_id, "Starting Cloudflare integration update");
-        let mut cloudflare_integration = cloudflare::CloudflareIntegration::new(execution_id);
-        let cloudflare_cache = cloudflare_integration.update_cache().await;
-        if let Some(_cloudflare_data) = &cloudflare_cache.data {
-            info!(execution_id = %execution_id, "Cloudflare integration update succeeded");
-            Some((
-                "cloudflare".to_string(),
-                IntegrationResult::Cloudflare(cloudflare_cache),
-            ))
-        } else {
-            error!(execution_id = %execution_id, "Cloudflare integration update failed");
-            None
-        }
-    };
+pub enum FetchOutcome {
+    Updated(FetchedData),
+    // The upstream answered 304 Not Modified or published the sync token already cached
+    Unchanged,
+}
 
-    // Digital Ocean integration update task
-    let digitalocean_task = async {
-        info!(execution_id = %execution_id, "Starting Digital Ocean integration update");
-        let mut digitalocean_integration = digitalocean::DigitalOceanIntegration::new(execution_id);
-        let digitalocean_cache = digitalocean_integration.update_cache().await;
-        if let Some(_digital_ocean_data) = &digitalocean_cache.data {
-            info!(execution_id = %execution_id, "Digital Ocean integration update succeeded");
-            Some((
-                "digitalocean".to_string(),
-                IntegrationResult::DigitalOcean(digitalocean_cache),
-            ))
-        } else {
-            error!(execution_id = %execution_id, "Digital Ocean integration update failed");
-            None
-        }
-    };
+#[async_trait]
+pub trait Integration: Send + Sync {
+    // Name used for cache keys, routes and query parameters
+    fn name(&self) -> &'static str;
 
-    // Fastly integration update task
-    let fastly_task = async {
-        info!(execution_id = %execution_id, "Starting Fastly integration update");
-        let mut fastly_integration = fastly::FastlyIntegration::new(execution_id);
-        let fastly_cache = fastly_integration.update_cache().await;
-        if let Some(_fastly_data) = &fastly_cache.data {
-            info!(execution_id = %execution_id, "Fastly integration update succeeded");
-            Some((
-                "fastly".to_string(),
-                IntegrationResult::Fastly(fastly_cache),
-            ))
-        } else {
-            error!(execution_id = %execution_id, "Fastly integration update failed");
-            None
-        }
-    };
+    // Human readable name used in logs and response messages
+    fn display_name(&self) -> &'static str;
 
-    // GCP integration update task
-    let gcp_task = async {
-        info!(execution_id = %execution_id, "Starting GCP integration update");
-        let mut gcp_integration = gcp::GcpIntegration::new(execution_id);
-        let gcp_cache = gcp_integration.update_cache().await;
-        if let Some(_gcp_data) = &gcp_cache.data {
-            info!(execution_id = %execution_id, "GCP integration update succeeded");
-            Some(("gcp".to_string(), IntegrationResult::Gcp(gcp_cache)))
-        } else {
-            error!(execution_id = %execution_id, "GCP integration update failed");
-            None
-        }
-    };
+    fn refresh_policy(&self) -> RefreshPolicy {
+        RefreshPolicy::default()
+    }
 
-    // Linode integration update task
-    let linode_task = async {
-        info!(execution_id = %execution_id, "Starting Linode integration update");
-        let mut linode_integration = linode::LinodeIntegration::new(execution_id);
-        let linode_cache = linode_integration.update_cache().await;
-        if let Some(_linode_data) = &linode_cache.data {
-            info!(execution_id = %execution_id, "Linode integration update succeeded");
-            Some((
-                "linode".to_string(),
-                IntegrationResult::Linode(linode_cache),
-            ))
-        } else {
-            error!(execution_id = %execution_id, "Linode integration update failed");
-            None
-        }
-    };
+    // Query parameters accepted by the provider endpoint
+    fn filter_schema(&self) -> FilterSchema;
 
-    // Oracle integration update task
-    let oracle_task = async {
-        info!(execution_id = %execution_id, "Starting Oracle integration update");
-        let mut oracle_integration = oracle::OracleIntegration::new(execution_id);
-        let oracle_cache = oracle_integration.update_cache().await;
-        if let Some(_oracle_data) = &oracle_cache.data {
-            info!(execution_id = %execution_id, "Oracle integration update succeeded");
-            Some((
-                "oracle".to_string(),
-                IntegrationResult::Oracle(oracle_cache),
-            ))
-        } else {
-            error!(execution_id = %execution_id, "Oracle integration update failed");
-            None
-        }
+    // Fetch and parse the upstream data, normalizing every published prefix
+    async fn fetch(
+        &self,
+        client: &HttpClient,
+        execution_id: Uuid,
+    ) -> Result<FetchOutcome, FetchError>;
+}
+
+lazy_static! {
+    // Every integration served by the API. Adding a provider means adding it here.
+    // Providers disabled in the config are left out entirely.
+    static ref REGISTRY: Vec<Arc<dyn Integration>> = {
+        let integrations: Vec<Arc<dyn Integration>> = vec![
+            Arc::new(aws::AwsIntegration),
+            Arc::new(azure::AzureIntegration),
+            Arc::new(cloudflare::CloudflareIntegration),
+            Arc::new(digitalocean::DigitalOceanIntegration),
+            Arc::new(fastly::FastlyIntegration),
+            Arc::new(gcp::GcpIntegration),
+            Arc::new(linode::LinodeIntegration),
+            Arc::new(oracle::OracleIntegration),
+        ];
+        integrations
+            .into_iter()
+            .filter(|integration| config::get().provider_enabled(integration.name()))
+            .collect()
     };
+}
 
-    // Wait for all integration tasks to complete
-    let (
-        aws_result,
-        azure_result,
-        cloudflare_result,
-        digitalocean_result,
-        fastly_result,
-        gcp_result,
-        linode_result,
-        oracle_result,
-    ) = tokio::join!(
-        aws_task,
-        azure_task,
-        cloudflare_task,
-        digitalocean_task,
-        fastly_task,
-        gcp_task,
-        linode_task,
-        oracle_task
-    );
+pub fn registry() -> &'static [Arc<dyn Integration>] {
+    &REGISTRY
+}
 
-    if let Some((integration_name, integration_result)) = aws_result {
-        all_data.insert(integration_name, integration_result);
-    }
+pub fn find(integration_name: &str) -> Option<&'static Arc<dyn Integration>> {
+    REGISTRY
+        .iter()
+        .find(|integration| integration.name().eq_ignore_ascii_case(integration_name))
+}
 
-    if let Some((integration_name, integration_result)) = azure_result {
-        all_data.insert(integration_name, integration_result);
-    }
+// Whether the sync token just published matches the one of the cached data
+pub fn sync_token_unchanged(integration_name: &str, sync_token: Option<&str>) -> bool {
+    sync_token.is_some_and(|sync_token| {
+        cache::get(integration_name).is_some_and(|cached| {
+            cached
+                .version
+                .as_ref()
+                .and_then(|version| version.sync_token.as_deref())
+                == Some(sync_token)
+        })
+    })
+}
 
-    if let Some((integration_name, integration_result)) = cloudflare_result {
-        all_data.insert(integration_name, integration_result);
+// Refresh policy of an integration, a per-provider config interval taking precedence
+pub fn refresh_policy(integration: &dyn Integration) -> RefreshPolicy {
+    let policy = integration.refresh_policy();
+    RefreshPolicy {
+        interval: config::get()
+            .provider_refresh_interval(integration.name())
+            .unwrap_or(policy.interval),
+        ..policy
     }
+}
 
-    if let Some((integration_name, integration_result)) = digitalocean_result {
-        all_data.insert(integration_name, integration_result);
-    }
+// Fetch a single integration, dropping malformed rows from its normalized prefixes.
+// Returns None when the upstream data is unchanged since the last fetch.
+pub async fn update_integration(
+    integration: &dyn Integration,
+    client: &HttpClient,
+    execution_id: Uuid,
+) -> Result<Option<IntegrationCache>, FetchError> {
+    info!(
+        integration_name = integration.name(),
+        execution_id = %execution_id,
+        "Starting {} integration update", integration.display_name()
+    );
 
-    if let Some((integration_name, integration_result)) = fastly_result {
-        all_data.insert(integration_name, integration_result);
+    let result = fetch_integration(integration, client, execution_id).await;
+    match &result {
+        Ok(Some(_)) => info!(
+            integration_name = integration.name(),
+            execution_id = %execution_id,
+            "{} integration update succeeded", integration.display_name()
+        ),
+        Ok(None) => info!(
+            integration_name = integration.name(),
+            execution_id = %execution_id,
+            "{} data unchanged", integration.display_name()
+        ),
+        Err(err) => {
+            error!(
+                integration_name = integration.name(),
+                execution_id = %execution_id,
+                error_kind = err.kind(),
+                "{} integration update failed: {}", integration.display_name(), err
+            );
+            http::clear_validators(integration.name());
+        }
     }
 
-    if let Some((integration_name, integration_result)) = gcp_result {
-        all_data.insert(integration_name, integration_result);
-    }
+    cache::record_attempt(integration.name(), result.as_ref().err().cloned());
+    result
+}
 
-    if let Some((integration_name, integration_result)) = linode_result {
-        all_data.insert(integration_name, integration_result);
-    }
+async fn fetch_integration(
+    integration: &dyn Integration,
+    client: &HttpClient,
+    execution_id: Uuid,
+) -> Result<Option<IntegrationCache>, FetchError> {
+    // Bound the whole fetch by the configured timeout
+    let timeout = config::get().provider_timeout(integration.name());
+    let fetched = match time::timeout(timeout, integration.fetch(client, execution_id))
+        .await
+        .unwrap_or_else(|_| {
+            Err(FetchError::Network(format!(
+                "Timed out after {}s",
+                timeout.as_secs()
+            )))
+        })? {
+        FetchOutcome::Updated(fetched) => fetched,
+        FetchOutcome::Unchanged => return Ok(None),
+    };
 
-    if let Some((integration_name, integration_result)) = oracle_result {
-        all_data.insert(integration_name, integration_result);
+    let mut prefixes = Vec::new();
+    let mut rejected = 0;
+    for result in fetched.prefixes {
+        match result {
+            Ok(prefix) => prefixes.push(prefix),
+            Err(err) => {
+                warn!(
+                    integration_name = integration.name(),
+                    execution_id = %execution_id,
+                    "Dropping invalid prefix: {}", err
+                );
+                rejected += 1;
+            }
+        }
     }
 
-    info!(execution_id = %execution_id, "Completed update for all integrations");
+    // Keep serving the previous data when the update looks truncated or stale
+    sanity::check_update(
+        integration.name(),
+        &prefixes,
+        fetched.version.as_ref(),
+        cache::get(integration.name()).as_deref(),
+    )?;
 
-    all_data
+    // Building the index of a large provider takes long enough to stall the async worker
+    let integration_cache =
+        task::spawn_blocking(move || IntegrationCache::new(prefixes, rejected, fetched.version))
+            .await
+            .expect("Prefix index build panicked");
+    Ok(Some(integration_cache))
 }
@@ -0,0 +1,130 @@
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Family {
+    Ipv4,
+    Ipv6,
+}
+
+impl Family {
+    pub fn of(network: &IpNet) -> Self {
+        match network {
+            IpNet::V4(_) => Family::Ipv4,
+            IpNet::V6(_) => Family::Ipv6,
+        }
+    }
+}
+
+impl fmt::Display for Family {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Family::Ipv4 => write!(f, "ipv4"),
+            Family::Ipv6 => write!(f, "ipv6"),
+        }
+    }
+}
+
+// Provider-independent view of a single published prefix
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct NormalizedPrefix {
+    pub provider: String,
+    #[serde(rename = "prefix")]
+    pub network: IpNet,
+    pub family: Family,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl NormalizedPrefix {
+    // Build a prefix for a provider from its published CIDR, validating it on the way in
+    pub fn new(provider: &str, prefix: &str) -> Result<Self, PrefixError> {
+        let network = parse_prefix(prefix)?;
+
+        Ok(NormalizedPrefix {
+            provider: provider.to_string(),
+            network,
+            family: Family::of(&network),
+            region: None,
+            service: None,
+            country: None,
+            attributes: BTreeMap::new(),
+        })
+    }
+
+    pub fn with_region(mut self, region: &str) -> Self {
+        self.region = non_empty(region);
+        self
+    }
+
+    pub fn with_service(mut self, service: &str) -> Self {
+        self.service = non_empty(service);
+        self
+    }
+
+    pub fn with_country(mut self, country: &str) -> Self {
+        self.country = non_empty(country);
+        self
+    }
+
+    pub fn with_attribute(mut self, key: &str, value: &str) -> Self {
+        if let Some(value) = non_empty(value) {
+            self.attributes.insert(key.to_string(), value);
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrefixError {
+    Empty,
+    Malformed(String),
+    HostBitsSet(String),
+}
+
+impl fmt::Display for PrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefixError::Empty => write!(f, "empty prefix"),
+            PrefixError::Malformed(value) => write!(f, "malformed prefix {:?}", value),
+            PrefixError::HostBitsSet(value) => write!(f, "prefix {:?} has host bits set", value),
+        }
+    }
+}
+
+// Parse a published CIDR into a typed network. The network's Display form is canonical
+// (lowercase, compressed IPv6), and prefixes with host bits set are rejected rather than
+// silently truncated.
+pub fn parse_prefix(value: &str) -> Result<IpNet, PrefixError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(PrefixError::Empty);
+    }
+
+    let network = value
+        .parse::<IpNet>()
+        .map_err(|_| PrefixError::Malformed(value.to_string()))?;
+    if network.trunc() != network {
+        return Err(PrefixError::HostBitsSet(value.to_string()));
+    }
+
+    Ok(network)
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
@@ -1,12 +1,19 @@
-use super::Integration;
-use crate::cache::IntegrationCache;
+use super::error::FetchError;
+use super::http::HttpClient;
+use super::normalized::{Family, NormalizedPrefix, PrefixError};
+use super::{FetchOutcome, FetchedData, Integration, RefreshPolicy};
+use crate::config;
+use crate::filter::{FamilyPolicy, FilterField, FilterMatch, FilterParam, FilterSchema};
 use async_trait::async_trait;
-use reqwest;
 use rocket::serde::json::serde_json;
 use serde::Deserialize;
-use tracing::{error, info};
+use std::time::Duration;
+use tracing::info;
 use uuid::Uuid;
 
+const ORACLE_URL: &str = "https://docs.oracle.com/en-us/iaas/tools/public_ip_ranges.json";
+const ORACLE_MIRROR_FILE: &str = "oracle.json";
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct OracleIpRanges {
     pub regions: Vec<OracleRegion>,
@@ -29,68 +36,89 @@ fn default_tags() -> Vec<String> {
     Vec::new()
 }
 
-pub struct OracleIntegration {
-    execution_id: Uuid,
-}
+const FILTERS: &[FilterParam] = &[
+    FilterParam {
+        name: "region",
+        field: FilterField::Region,
+        matching: FilterMatch::Exact,
+    },
+    FilterParam {
+        name: "tag",
+        field: FilterField::Attribute("tags"),
+        matching: FilterMatch::ListItem,
+    },
+];
+
+pub struct OracleIntegration;
 
 impl OracleIntegration {
-    pub fn new(execution_id: Uuid) -> Self {
-        OracleIntegration { execution_id }
+    fn parse(&self, data: &str) -> Result<OracleIpRanges, FetchError> {
+        Ok(serde_json::from_str(data)?)
     }
 
-    async fn fetch_ip_ranges() -> Option<Vec<OracleRegion>> {
-        let url = "https://docs.oracle.com/en-us/iaas/tools/public_ip_ranges.json";
-        let response = reqwest::get(url).await.ok()?.text().await.ok()?;
-        let ip_ranges: OracleIpRanges = match serde_json::from_str(&response) {
-            Ok(data) => data,
-            Err(err) => {
-                error!(
-                    "Failed to parse Oracle IP ranges JSON: {}. Response: {}",
-                    err, response
-                );
-                return None;
-            }
-        };
-
-        Some(ip_ranges.regions)
+    fn normalize(&self, data: &OracleIpRanges) -> Vec<Result<NormalizedPrefix, PrefixError>> {
+        data.regions
+            .iter()
+            .flat_map(|oracle_region| {
+                oracle_region.cidrs.iter().map(|cidr| {
+                    NormalizedPrefix::new(self.name(), &cidr.cidr).map(|normalized| {
+                        normalized
+                            .with_region(&oracle_region.region)
+                            .with_attribute("tags", &cidr.tags.join(","))
+                    })
+                })
+            })
+            .collect()
     }
 }
 
 #[async_trait]
 impl Integration for OracleIntegration {
-    type DataModel = OracleIpRanges;
-
-    async fn update_cache(&mut self) -> IntegrationCache<Self::DataModel> {
-        let ip_ranges = match Self::fetch_ip_ranges().await {
-            Some(ranges) => ranges,
-            None => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to fetch Oracle IP ranges"
-                );
-                return IntegrationCache::new(None);
-            }
-        };
+    fn name(&self) -> &'static str {
+        "oracle"
+    }
 
-        let data_model = OracleIpRanges { regions: ip_ranges };
-        info!(
-            execution_id = %self.execution_id,
-            "Oracle cache updated"
-        );
+    fn display_name(&self) -> &'static str {
+        "Oracle"
+    }
 
-        IntegrationCache::new(Some(data_model))
+    // Oracle's ranges rarely change, so they are polled at most hourly
+    fn refresh_policy(&self) -> RefreshPolicy {
+        let policy = RefreshPolicy::default();
+        RefreshPolicy {
+            interval: policy.interval.max(Duration::from_secs(3600)),
+            ..policy
+        }
     }
 
-    fn parse(&self, data: &str) -> Option<Self::DataModel> {
-        match serde_json::from_str(data) {
-            Ok(parsed_data) => Some(parsed_data),
-            Err(err) => {
-                error!(
-                    execution_id = %self.execution_id,
-                    "Failed to parse Oracle JSON: {}", err
-                );
-                None
-            }
+    fn filter_schema(&self) -> FilterSchema {
+        // Oracle has no family flags, so both families are returned unless one is requested
+        FilterSchema {
+            params: FILTERS,
+            family: FamilyPolicy::DefaultTo(&[Family::Ipv4, Family::Ipv6]),
         }
     }
+
+    async fn fetch(
+        &self,
+        client: &HttpClient,
+        execution_id: Uuid,
+    ) -> Result<FetchOutcome, FetchError> {
+        let url = config::upstream_url(self.name(), "url", ORACLE_URL, ORACLE_MIRROR_FILE);
+        let response = match client.get_if_modified(self.name(), &url).await? {
+            Some(response) => response,
+            None => return Ok(FetchOutcome::Unchanged),
+        };
+
+        let data_model = self.parse(&response)?;
+        info!(
+            execution_id = %execution_id,
+            "Oracle data fetched"
+        );
+
+        Ok(FetchOutcome::Updated(FetchedData {
+            prefixes: self.normalize(&data_model),
+            version: None,
+        }))
+    }
 }
@@ -0,0 +1,74 @@
+use super::error::FetchError;
+use super::normalized::NormalizedPrefix;
+use super::UpstreamVersion;
+use crate::cache::IntegrationCache;
+use crate::config;
+
+// Reject error pages served in place of a data document, e.g. by a CDN in front of the upstream.
+// None of the JSON, CSV or plain-text formats fetched start with markup.
+pub fn check_document(body: &str) -> Result<(), FetchError> {
+    if body.trim_start().starts_with('<') {
+        return Err(FetchError::SanityCheck(
+            "Upstream returned an HTML page".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Compare an update with the served data before it replaces it
+pub fn check_update(
+    integration_name: &str,
+    prefixes: &[NormalizedPrefix],
+    version: Option<&UpstreamVersion>,
+    previous: Option<&IntegrationCache>,
+) -> Result<(), FetchError> {
+    let settings = config::get();
+
+    let min_prefixes = settings.provider_min_prefixes(integration_name);
+    if prefixes.len() < min_prefixes {
+        return Err(FetchError::SanityCheck(format!(
+            "{} prefixes, fewer than the minimum of {}",
+            prefixes.len(),
+            min_prefixes
+        )));
+    }
+
+    let previous = match previous {
+        Some(previous) => previous,
+        None => return Ok(()),
+    };
+
+    let previous_count = previous.prefixes().len();
+    let max_shrink_percent = settings.provider_max_shrink_percent(integration_name);
+    if previous_count > 0 && prefixes.len() < previous_count {
+        let shrink_percent =
+            (previous_count - prefixes.len()) as f64 * 100.0 / previous_count as f64;
+        if shrink_percent > max_shrink_percent {
+            return Err(FetchError::SanityCheck(format!(
+                "Prefix count dropped from {} to {}, more than {}%",
+                previous_count,
+                prefixes.len(),
+                max_shrink_percent
+            )));
+        }
+    }
+
+    // Numeric sync tokens only ever grow, an older one means a stale copy of the document
+    let sync_token = |version: Option<&UpstreamVersion>| {
+        version
+            .and_then(|version| version.sync_token.as_deref())
+            .and_then(|sync_token| sync_token.parse::<u64>().ok())
+    };
+    if let (Some(token), Some(previous_token)) =
+        (sync_token(version), sync_token(previous.version.as_ref()))
+    {
+        if token < previous_token {
+            return Err(FetchError::SanityCheck(format!(
+                "Sync token {} is older than the served {}",
+                token, previous_token
+            )));
+        }
+    }
+
+    Ok(())
+}
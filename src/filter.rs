@@ -0,0 +1,229 @@
+use crate::fetchers::normalized::{Family, NormalizedPrefix};
+use crate::fetchers::registry;
+use std::collections::HashMap;
+
+// Normalized field a provider query parameter is matched against
+#[derive(Debug, Clone, Copy)]
+pub enum FilterField {
+    Region,
+    Service,
+    Country,
+    Attribute(&'static str),
+}
+
+impl FilterField {
+    pub fn value<'a>(&self, prefix: &'a NormalizedPrefix) -> Option<&'a str> {
+        match self {
+            FilterField::Region => prefix.region.as_deref(),
+            FilterField::Service => prefix.service.as_deref(),
+            FilterField::Country => prefix.country.as_deref(),
+            FilterField::Attribute(key) => prefix.attributes.get(*key).map(String::as_str),
+        }
+    }
+}
+
+// How a query parameter value is compared with the field, always case-insensitively
+#[derive(Debug, Clone, Copy)]
+pub enum FilterMatch {
+    Exact,
+    Contains,
+    // The field holds a comma-separated list and any item may match
+    ListItem,
+}
+
+impl FilterMatch {
+    pub fn matches(&self, value: &str, param: &str) -> bool {
+        match self {
+            FilterMatch::Exact => value.eq_ignore_ascii_case(param),
+            FilterMatch::Contains => value.to_lowercase().contains(&param.to_lowercase()),
+            FilterMatch::ListItem => value
+                .split(',')
+                .any(|item| item.eq_ignore_ascii_case(param)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FilterParam {
+    pub name: &'static str,
+    pub field: FilterField,
+    pub matching: FilterMatch,
+}
+
+// How the ipv4/ipv6 flags of a provider endpoint behave
+#[derive(Debug, Clone, Copy)]
+pub enum FamilyPolicy {
+    // At least one of the flags must be enabled
+    Required,
+    // Families returned when neither flag is given
+    DefaultTo(&'static [Family]),
+}
+
+// Query parameters a provider endpoint accepts, declared by each integration
+#[derive(Debug, Clone, Copy)]
+pub struct FilterSchema {
+    pub params: &'static [FilterParam],
+    pub family: FamilyPolicy,
+}
+
+impl FilterSchema {
+    // Resolve the query parameters of a request against the schema
+    pub fn resolve(&self, params: &HashMap<String, String>) -> Result<SchemaQuery, String> {
+        let flag = |name: &str| -> Result<Option<bool>, String> {
+            params
+                .get(name)
+                .map(|value| {
+                    parse_flag(value).ok_or(format!("Invalid value for {}: {}", name, value))
+                })
+                .transpose()
+        };
+
+        let (ipv4, ipv6) = (flag("ipv4")?, flag("ipv6")?);
+        let flags = [(ipv4, Family::Ipv4), (ipv6, Family::Ipv6)];
+        let families: Vec<Family> = match self.family {
+            // Without an enabled flag, the defaults are only narrowed by the disabled ones
+            FamilyPolicy::DefaultTo(families)
+                if !flags.iter().any(|(flag, _)| *flag == Some(true)) =>
+            {
+                families
+                    .iter()
+                    .copied()
+                    .filter(|family| {
+                        !flags
+                            .iter()
+                            .any(|(flag, flagged)| *flag == Some(false) && flagged == family)
+                    })
+                    .collect()
+            }
+            _ => flags
+                .into_iter()
+                .filter(|(flag, _)| flag.unwrap_or(false))
+                .map(|(_, family)| family)
+                .collect(),
+        };
+
+        // Check if both ipv4 and ipv6 flags are false or not set
+        if families.is_empty() {
+            return Err("Either ipv4 or ipv6 must be specified".to_string());
+        }
+
+        let conditions = self
+            .params
+            .iter()
+            .filter_map(|param| params.get(param.name).map(|value| (*param, value.clone())))
+            .collect();
+
+        Ok(SchemaQuery {
+            conditions,
+            families,
+        })
+    }
+}
+
+// A provider endpoint request resolved against the provider's filter schema
+#[derive(Debug, Clone)]
+pub struct SchemaQuery {
+    pub conditions: Vec<(FilterParam, String)>,
+    pub families: Vec<Family>,
+}
+
+impl SchemaQuery {
+    pub fn matches(&self, prefix: &NormalizedPrefix) -> bool {
+        self.families.contains(&prefix.family)
+            && self.conditions.iter().all(|(param, value)| {
+                param
+                    .field
+                    .value(prefix)
+                    .is_some_and(|field| param.matching.matches(field, value))
+            })
+    }
+}
+
+// Provider-independent filter applied to normalized prefixes. Every list is a set of
+// alternatives and an empty list matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct PrefixFilter {
+    pub providers: Vec<String>,
+    pub families: Vec<Family>,
+    pub regions: Vec<String>,
+    pub services: Vec<String>,
+    pub countries: Vec<String>,
+}
+
+impl PrefixFilter {
+    // Build a filter from comma-separated query parameters, rejecting unknown providers and families
+    pub fn parse(
+        provider: Option<&str>,
+        family: Option<&str>,
+        region: Option<&str>,
+        service: Option<&str>,
+        country: Option<&str>,
+    ) -> Result<Self, String> {
+        let providers = split_list(provider);
+        if let Some(unknown) = providers.iter().find(|provider| {
+            !registry()
+                .iter()
+                .any(|integration| integration.name() == provider.as_str())
+        }) {
+            return Err(format!("Unknown provider: {}", unknown));
+        }
+
+        let families = split_list(family)
+            .iter()
+            .map(|family| match family.as_str() {
+                "ipv4" => Ok(Family::Ipv4),
+                "ipv6" => Ok(Family::Ipv6),
+                _ => Err(format!("Unknown family: {}", family)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(PrefixFilter {
+            providers,
+            families,
+            regions: split_list(region),
+            services: split_list(service),
+            countries: split_list(country),
+        })
+    }
+
+    // Providers selected by the filter, in a stable order
+    pub fn selected_providers(&self) -> Vec<&'static str> {
+        registry()
+            .iter()
+            .map(|integration| integration.name())
+            .filter(|name| self.providers.is_empty() || self.providers.iter().any(|p| p == name))
+            .collect()
+    }
+
+    pub fn matches(&self, prefix: &NormalizedPrefix) -> bool {
+        (self.providers.is_empty() || self.providers.contains(&prefix.provider))
+            && (self.families.is_empty() || self.families.contains(&prefix.family))
+            && matches_any(&self.regions, prefix.region.as_deref())
+            && matches_any(&self.services, prefix.service.as_deref())
+            && matches_any(&self.countries, prefix.country.as_deref())
+    }
+}
+
+// Split a comma-separated parameter into lowercase, non-empty values
+fn split_list(value: Option<&str>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(|item| item.trim().to_lowercase())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn matches_any(values: &[String], field: Option<&str>) -> bool {
+    values.is_empty()
+        || field.is_some_and(|field| values.iter().any(|value| field.eq_ignore_ascii_case(value)))
+}
+
+// Parse a boolean query flag the way Rocket does for form fields
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "on" | "yes" | "1" | "" => Some(true),
+        "false" | "off" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
@@ -0,0 +1,97 @@
+use crate::fetchers::{find, registry};
+use crate::history::{self, ProviderChanges, Since};
+use rocket::get;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use serde::Serialize;
+use tracing::{error, info};
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct ChangesApiResponse<T> {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[get("/v1/changes?<provider>&<since>")]
+pub fn query_changes_data(
+    provider: Option<String>,
+    since: Option<String>,
+) -> (Status, Json<ChangesApiResponse<Vec<ProviderChanges>>>) {
+    // Generate a unique request ID
+    let request_id = Uuid::new_v4();
+
+    info!(
+        request_id = %request_id,
+        provider = provider.clone().map(|s| s.to_lowercase()),
+        since = since.clone(),
+        "Received changes request"
+    );
+
+    let error = |status: Status, message: String| {
+        error!(
+            request_id = %request_id,
+            "Failed to retrieve changes: {}", message
+        );
+        (
+            status,
+            Json(ChangesApiResponse {
+                status: "error".to_string(),
+                data: None,
+                message: Some(message),
+            }),
+        )
+    };
+
+    let since = since.as_deref().map(Since::parse);
+
+    // A single provider reports why it has no changes, all providers skip those without history
+    let changes = match provider.as_deref() {
+        Some(provider) => {
+            let integration = match find(provider) {
+                Some(integration) => integration,
+                None => {
+                    return error(
+                        Status::BadRequest,
+                        format!("Unknown provider: {}", provider),
+                    )
+                }
+            };
+            match history::changes(integration.name(), since.as_ref()) {
+                Ok(changes) => vec![changes],
+                Err(message) => return error(Status::NotFound, message),
+            }
+        }
+        None => {
+            if let Some(Since::Token(_)) = since {
+                return error(
+                    Status::BadRequest,
+                    "A sync token requires a provider, otherwise since must be an RFC 3339 time"
+                        .to_string(),
+                );
+            }
+            registry()
+                .iter()
+                .filter_map(|integration| history::changes(integration.name(), since.as_ref()).ok())
+                .collect()
+        }
+    };
+
+    info!(
+        request_id = %request_id,
+        generations = changes.iter().map(|changes| changes.generations.len()).sum::<usize>(),
+        "Changes found for request"
+    );
+
+    (
+        Status::Ok,
+        Json(ChangesApiResponse {
+            status: "success".to_string(),
+            data: Some(changes),
+            message: None,
+        }),
+    )
+}
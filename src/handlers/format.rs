@@ -0,0 +1,214 @@
+use crate::export::{self, ExportFormat, ExportOptions};
+use crate::fetchers::normalized::NormalizedPrefix;
+use rocket::futures::stream::{self, Iter};
+use rocket::http::{ContentType, MediaType};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::stream::TextStream;
+use rocket::response::{self, Responder};
+use rocket::serde::json::{serde_json, Json};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::iter::Map;
+use std::vec;
+
+// Representations the query endpoints can return their prefixes in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    // The JSON envelope every endpoint returns by default
+    Json,
+    // One CIDR per line
+    Text,
+    // One prefix per row, with its metadata in columns
+    Csv,
+    // One JSON prefix per line
+    Ndjson,
+    // A configuration file built from the prefixes
+    Export(ExportFormat),
+}
+
+impl OutputFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(OutputFormat::Json),
+            "text" | "txt" | "plain" => Some(OutputFormat::Text),
+            "csv" => Some(OutputFormat::Csv),
+            "ndjson" | "jsonl" => Some(OutputFormat::Ndjson),
+            _ => ExportFormat::from_name(name).map(OutputFormat::Export),
+        }
+    }
+
+    fn content_type(self) -> ContentType {
+        match self {
+            OutputFormat::Json => ContentType::JSON,
+            OutputFormat::Text => ContentType::Plain,
+            OutputFormat::Csv => ContentType::CSV,
+            OutputFormat::Ndjson => ContentType::new("application", "x-ndjson"),
+            OutputFormat::Export(format) => format.content_type(),
+        }
+    }
+
+    fn from_media_type(media_type: &MediaType) -> Option<Self> {
+        match (media_type.top().as_str(), media_type.sub().as_str()) {
+            ("application", "json") | ("*", "*") | ("application", "*") => Some(OutputFormat::Json),
+            ("text", "plain") | ("text", "*") => Some(OutputFormat::Text),
+            ("text", "csv") => Some(OutputFormat::Csv),
+            ("application", "x-ndjson") | ("application", "ndjson") => Some(OutputFormat::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+// Output format of a request, with the options of export formats
+pub struct Output {
+    pub format: OutputFormat,
+    pub export: ExportOptions,
+}
+
+// Output of a request: its format parameter, else its most preferred supported Accept media
+// type, else JSON
+pub struct Negotiated(pub Result<Output, String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Negotiated {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        if let Some(format) = request.query_value::<&str>("format") {
+            let format = format.unwrap_or_default();
+            let output = match OutputFormat::from_name(format) {
+                Some(OutputFormat::Export(export_format)) => {
+                    ExportOptions::parse(export_format, |key| {
+                        request.query_value(key).and_then(Result::ok)
+                    })
+                    .map(|export| Output {
+                        format: OutputFormat::Export(export_format),
+                        export,
+                    })
+                }
+                Some(format) => Ok(Output {
+                    format,
+                    export: ExportOptions::default(),
+                }),
+                None => Err(format!("Unknown format: {}", format)),
+            };
+            return Outcome::Success(Negotiated(output));
+        }
+
+        let mut media_types: Vec<(f32, &MediaType)> = request
+            .accept()
+            .map(|accept| {
+                accept
+                    .iter()
+                    .map(|media_type| (media_type.weight_or(1.0), media_type.media_type()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        media_types.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let format = media_types
+            .iter()
+            .find_map(|(_, media_type)| OutputFormat::from_media_type(media_type))
+            .unwrap_or(OutputFormat::Json);
+        Outcome::Success(Negotiated(Ok(Output {
+            format,
+            export: ExportOptions::default(),
+        })))
+    }
+}
+
+// NDJSON lines, each serialized only when the response body reaches it
+type NdjsonLines = Iter<Map<vec::IntoIter<NormalizedPrefix>, fn(NormalizedPrefix) -> String>>;
+
+// Response of a query endpoint, either its JSON envelope or its prefixes in another format
+pub enum Formatted<T> {
+    Json(Json<T>),
+    Body(OutputFormat, String),
+    // Large result sets are sent line by line rather than built up as one body
+    Stream(OutputFormat, TextStream<NdjsonLines>),
+}
+
+impl<'r, T: Serialize> Responder<'r, 'r> for Formatted<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'r> {
+        match self {
+            Formatted::Json(json) => json.respond_to(request),
+            Formatted::Body(format, body) => (format.content_type(), body).respond_to(request),
+            Formatted::Stream(format, lines) => (format.content_type(), lines).respond_to(request),
+        }
+    }
+}
+
+impl<T> Formatted<T> {
+    // Render the prefixes of a successful query, building the JSON envelope only when asked for it.
+    // The filters are the query parameters recorded in export headers.
+    pub fn render<'a>(
+        output: Output,
+        filters: &[(&str, &str)],
+        prefixes: impl IntoIterator<Item = &'a NormalizedPrefix>,
+        envelope: impl FnOnce() -> T,
+    ) -> Self {
+        let format = output.format;
+        match format {
+            OutputFormat::Json => Formatted::Json(Json(envelope())),
+            OutputFormat::Text => {
+                // A prefix published for several services is listed once
+                let mut seen = HashSet::new();
+                let body: String = prefixes
+                    .into_iter()
+                    .filter(|prefix| seen.insert(prefix.network))
+                    .map(|prefix| format!("{}\n", prefix.network))
+                    .collect();
+                Formatted::Body(format, body)
+            }
+            OutputFormat::Csv => {
+                let mut body =
+                    "provider,prefix,family,region,service,country,attributes\n".to_string();
+                for prefix in prefixes {
+                    let attributes: Vec<String> = prefix
+                        .attributes
+                        .iter()
+                        .map(|(key, value)| format!("{}={}", key, value))
+                        .collect();
+                    let row = [
+                        prefix.provider.clone(),
+                        prefix.network.to_string(),
+                        prefix.family.to_string(),
+                        prefix.region.clone().unwrap_or_default(),
+                        prefix.service.clone().unwrap_or_default(),
+                        prefix.country.clone().unwrap_or_default(),
+                        attributes.join(";"),
+                    ];
+                    let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+                    body.push_str(&row.join(","));
+                    body.push('\n');
+                }
+                Formatted::Body(format, body)
+            }
+            OutputFormat::Ndjson => {
+                let prefixes: Vec<NormalizedPrefix> = prefixes.into_iter().cloned().collect();
+                let lines = prefixes
+                    .into_iter()
+                    .map(ndjson_line as fn(NormalizedPrefix) -> String);
+                Formatted::Stream(format, TextStream(stream::iter(lines)))
+            }
+            OutputFormat::Export(export_format) => Formatted::Body(
+                format,
+                export::render(export_format, &output.export, filters, prefixes),
+            ),
+        }
+    }
+}
+
+fn ndjson_line(prefix: NormalizedPrefix) -> String {
+    serde_json::to_string(&prefix)
+        .map(|line| line + "\n")
+        .unwrap_or_default()
+}
+
+// Quote a CSV field when it holds a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
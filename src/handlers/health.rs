@@ -1,15 +1,65 @@
+use crate::cache;
+use crate::fetchers::registry;
 use rocket::get;
+use rocket::http::Status;
 use rocket::serde::json::Json;
 use serde::Serialize;
 
 #[derive(Serialize)]
 pub struct HealthResponse {
     status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    providers: Option<Vec<ProviderReadiness>>,
+}
+
+#[derive(Serialize)]
+pub struct ProviderReadiness {
+    provider: &'static str,
+    loaded: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_error: Option<String>,
 }
 
 #[get("/health")]
 pub fn health_check() -> Json<HealthResponse> {
+    live_check()
+}
+
+// The process is up and serving requests, whether or not data has been loaded
+#[get("/health/live")]
+pub fn live_check() -> Json<HealthResponse> {
     Json(HealthResponse {
         status: "ok".to_string(),
+        providers: None,
     })
 }
+
+// Ready once every provider has loaded data at least once
+#[get("/health/ready")]
+pub fn ready_check() -> (Status, Json<HealthResponse>) {
+    let providers: Vec<ProviderReadiness> = registry()
+        .iter()
+        .map(|integration| ProviderReadiness {
+            provider: integration.name(),
+            loaded: cache::get(integration.name()).is_some(),
+            last_error: cache::last_attempt(integration.name())
+                .and_then(|attempt| attempt.error)
+                .map(|error| error.to_string()),
+        })
+        .collect();
+
+    let ready = providers.iter().all(|provider| provider.loaded);
+    let (status, label) = if ready {
+        (Status::Ok, "ok")
+    } else {
+        (Status::ServiceUnavailable, "unavailable")
+    };
+
+    (
+        status,
+        Json(HealthResponse {
+            status: label.to_string(),
+            providers: Some(providers),
+        }),
+    )
+}
@@ -0,0 +1,144 @@
+use crate::cache;
+use crate::fetchers::find;
+use crate::fetchers::normalized::NormalizedPrefix;
+use crate::handlers::format::{Formatted, Negotiated};
+use crate::index::{covering_all, longest_match_all, parse_network};
+use rocket::get;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use serde::Serialize;
+use tracing::{error, info};
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct LookupApiResponse<T> {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[get("/v1/lookup?<ip>&<provider>&<longest>")]
+pub fn query_lookup_data(
+    ip: Option<String>,
+    provider: Option<String>,
+    longest: Option<bool>,
+    format: Negotiated,
+) -> (Status, Formatted<LookupApiResponse<Vec<NormalizedPrefix>>>) {
+    // Generate a unique request ID
+    let request_id = Uuid::new_v4();
+
+    // Log the start of the request with structured fields for received parameters
+    info!(
+        request_id = %request_id,
+        ip = ip.clone(),
+        provider = provider.clone().map(|s| s.to_lowercase()),
+        longest = longest.unwrap_or(false),
+        "Received lookup request"
+    );
+
+    // Reject unknown output formats before doing any work
+    let format = match format.0 {
+        Ok(format) => format,
+        Err(message) => {
+            return (
+                Status::BadRequest,
+                Formatted::Json(Json(LookupApiResponse {
+                    status: "error".to_string(),
+                    data: None,
+                    message: Some(message),
+                })),
+            );
+        }
+    };
+
+    // Parse the requested address or CIDR, rejecting anything that is not valid IPv4 or IPv6
+    let network = match ip.as_deref().and_then(parse_network) {
+        Some(network) => network,
+        None => {
+            return (
+                Status::BadRequest,
+                Formatted::Json(Json(LookupApiResponse {
+                    status: "error".to_string(),
+                    data: None,
+                    message: Some("A valid ip address or CIDR must be specified".to_string()),
+                })),
+            );
+        }
+    };
+
+    // Resolve the provider to search, rejecting unknown ones like the other query endpoints
+    let integration = match provider.as_deref() {
+        Some(name) => match find(name) {
+            Some(integration) => Some(integration),
+            None => {
+                return (
+                    Status::BadRequest,
+                    Formatted::Json(Json(LookupApiResponse {
+                        status: "error".to_string(),
+                        data: None,
+                        message: Some(format!("Unknown provider: {}", name)),
+                    })),
+                );
+            }
+        },
+        None => None,
+    };
+
+    // Search either a single provider index or the indexes of every provider
+    let longest_only = longest.unwrap_or(false);
+    let records = match integration {
+        Some(integration) => cache::get(integration.name()).map_or_else(Vec::new, |cached| {
+            if longest_only {
+                cached.index.longest_match(&network)
+            } else {
+                cached.index.covering(&network)
+            }
+        }),
+        None if longest_only => longest_match_all(&network),
+        None => covering_all(&network),
+    };
+    let matches: Vec<NormalizedPrefix> = records
+        .iter()
+        .map(|record| record.as_ref().clone())
+        .collect();
+
+    // If any prefix matched, return them in the requested format
+    if !matches.is_empty() {
+        info!(
+            request_id = %request_id,
+            matches = matches.len(),
+            "Lookup matches found for request"
+        );
+        let longest = longest.map(|longest| longest.to_string());
+        let filters: Vec<(&str, &str)> =
+            [("ip", &ip), ("provider", &provider), ("longest", &longest)]
+                .into_iter()
+                .filter_map(|(key, value)| value.as_deref().map(|value| (key, value)))
+                .collect();
+        return (
+            Status::Ok,
+            Formatted::render(format, &filters, matches.iter(), || LookupApiResponse {
+                status: "success".to_string(),
+                data: Some(matches.clone()),
+                message: None,
+            }),
+        );
+    }
+
+    // Log that no provider owns the address
+    error!(
+        request_id = %request_id,
+        "No provider prefix found for lookup"
+    );
+
+    (
+        Status::NotFound,
+        Formatted::Json(Json(LookupApiResponse {
+            status: "error".to_string(),
+            data: None,
+            message: Some("No matching prefixes found".to_string()),
+        })),
+    )
+}
@@ -1,31 +1,37 @@
-pub mod aws;
-pub mod azure;
-pub mod cloudflare;
-pub mod digitalocean;
-pub mod fastly;
-pub mod gcp;
+pub mod changes;
+pub mod format;
 pub mod health;
-pub mod linode;
-pub mod oracle;
+pub mod lookup;
+pub mod providers;
+pub mod ranges;
+pub mod status;
+pub mod stream;
+pub mod webhooks;
 
 use crate::handlers::{
-    aws::query_aws_data, azure::query_azure_data, cloudflare::query_cloudflare_data,
-    digitalocean::query_digitalocean_data, fastly::query_fastly_data, gcp::query_gcp_data,
-    health::health_check, linode::query_linode_data, oracle::query_oracle_data,
+    changes::query_changes_data,
+    health::{health_check, live_check, ready_check},
+    lookup::query_lookup_data,
+    providers::query_provider_data,
+    ranges::query_ranges_data,
+    status::query_status_data,
+    stream::stream_changes,
+    webhooks::query_webhook_deliveries,
 };
 
 use rocket::{routes, Route};
 
 pub fn routes() -> Vec<Route> {
     routes![
-        query_aws_data,
-        query_azure_data,
-        query_cloudflare_data,
-        query_digitalocean_data,
-        query_fastly_data,
-        query_gcp_data,
-        query_linode_data,
+        query_changes_data,
         health_check,
-        query_oracle_data
+        live_check,
+        ready_check,
+        query_lookup_data,
+        query_provider_data,
+        query_ranges_data,
+        query_status_data,
+        stream_changes,
+        query_webhook_deliveries
     ]
 }
@@ -0,0 +1,147 @@
+use crate::cache;
+use crate::fetchers::find;
+use crate::fetchers::normalized::{Family, NormalizedPrefix};
+use crate::filter::FamilyPolicy;
+use crate::handlers::format::{Formatted, Negotiated, OutputFormat};
+use rocket::get;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use serde::Serialize;
+use std::collections::HashMap;
+use tracing::{error, info};
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct ProviderApiResponse<T> {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[get("/v1/<provider>?<params..>")]
+pub fn query_provider_data(
+    provider: &str,
+    params: HashMap<String, String>,
+    format: Negotiated,
+) -> (Status, Formatted<ProviderApiResponse<Vec<String>>>) {
+    // Generate a unique request ID
+    let request_id = Uuid::new_v4();
+
+    // Log the start of the request with the received parameters
+    info!(
+        request_id = %request_id,
+        provider = provider.to_lowercase(),
+        params = ?params,
+        "Received request"
+    );
+
+    // Reject unknown output formats before doing any work
+    let format = match format.0 {
+        Ok(format) => format,
+        Err(message) => {
+            return (
+                Status::BadRequest,
+                Formatted::Json(Json(ProviderApiResponse {
+                    status: "error".to_string(),
+                    data: None,
+                    message: Some(message),
+                })),
+            );
+        }
+    };
+
+    // Resolve the integration serving this endpoint from the registry
+    let integration = match find(provider) {
+        Some(integration) => integration,
+        None => {
+            return (
+                Status::NotFound,
+                Formatted::Json(Json(ProviderApiResponse {
+                    status: "error".to_string(),
+                    data: None,
+                    message: Some(format!("Unknown provider: {}", provider)),
+                })),
+            );
+        }
+    };
+
+    // Exports cover both families unless the ipv4 or ipv6 flags narrow them
+    let mut schema = integration.filter_schema();
+    if let OutputFormat::Export(_) = format.format {
+        schema.family = FamilyPolicy::DefaultTo(&[Family::Ipv4, Family::Ipv6]);
+    }
+
+    // Validate the query parameters against the integration's filter schema
+    let query = match schema.resolve(&params) {
+        Ok(query) => query,
+        Err(message) => {
+            return (
+                Status::BadRequest,
+                Formatted::Json(Json(ProviderApiResponse {
+                    status: "error".to_string(),
+                    data: None,
+                    message: Some(message),
+                })),
+            );
+        }
+    };
+
+    // Access the integration cache from the global cache
+    if let Some(cached) = cache::get(integration.name()) {
+        // Filter the validated prefixes based on the provided parameters
+        let filtered_data: Vec<&NormalizedPrefix> = cached
+            .prefixes()
+            .iter()
+            .map(|prefix| prefix.as_ref())
+            .filter(|prefix| query.matches(prefix))
+            .collect();
+
+        // Render the filtered data in the requested format
+        if !filtered_data.is_empty() {
+            // Exports record only the parameters the schema resolved into filters
+            let families: Vec<String> = query.families.iter().map(Family::to_string).collect();
+            let families = families.join(",");
+            let mut filters: Vec<(&str, &str)> = query
+                .conditions
+                .iter()
+                .map(|(param, value)| (param.name, value.as_str()))
+                .collect();
+            filters.push(("family", &families));
+            info!(
+                request_id = %request_id,
+                "{} data found for request", integration.display_name()
+            );
+            return (
+                Status::Ok,
+                Formatted::render(format, &filters, filtered_data.iter().copied(), || {
+                    ProviderApiResponse {
+                        status: "success".to_string(),
+                        data: Some(
+                            filtered_data
+                                .iter()
+                                .map(|prefix| prefix.network.to_string())
+                                .collect(),
+                        ),
+                        message: None,
+                    }
+                }),
+            );
+        }
+    }
+
+    // Log failure to retrieve provider data
+    error!(
+        request_id = %request_id,
+        "Failed to retrieve {} data", integration.display_name()
+    );
+    (
+        Status::NotFound,
+        Formatted::Json(Json(ProviderApiResponse {
+            status: "error".to_string(),
+            data: None,
+            message: Some(format!("{} data not found", integration.display_name())),
+        })),
+    )
+}
@@ -0,0 +1,167 @@
+use crate::cache;
+use crate::fetchers::normalized::{Family, NormalizedPrefix};
+use crate::filter::PrefixFilter;
+use crate::handlers::format::{Formatted, Negotiated};
+use ipnet::IpNet;
+use rocket::get;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct RangesApiResponse<T> {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RangeEntry {
+    pub prefix: IpNet,
+    pub family: Family,
+    pub provider: String,
+}
+
+#[get("/v1/ranges?<provider>&<family>&<region>&<service>&<country>")]
+pub fn query_ranges_data(
+    provider: Option<String>,
+    family: Option<String>,
+    region: Option<String>,
+    service: Option<String>,
+    country: Option<String>,
+    format: Negotiated,
+) -> (Status, Formatted<RangesApiResponse<Vec<RangeEntry>>>) {
+    // Generate a unique request ID
+    let request_id = Uuid::new_v4();
+
+    // Log the start of the request with structured fields for received parameters
+    info!(
+        request_id = %request_id,
+        provider = provider.clone().map(|s| s.to_lowercase()),
+        family = family.clone().map(|s| s.to_lowercase()),
+        region = region.clone().map(|s| s.to_lowercase()),
+        service = service.clone().map(|s| s.to_lowercase()),
+        country = country.clone().map(|s| s.to_lowercase()),
+        "Received ranges request"
+    );
+
+    // Reject unknown output formats before doing any work
+    let format = match format.0 {
+        Ok(format) => format,
+        Err(message) => {
+            return (
+                Status::BadRequest,
+                Formatted::Json(Json(RangesApiResponse {
+                    status: "error".to_string(),
+                    data: None,
+                    message: Some(message),
+                })),
+            );
+        }
+    };
+
+    // Build the shared filter, rejecting unknown providers or families
+    let filter = match PrefixFilter::parse(
+        provider.as_deref(),
+        family.as_deref(),
+        region.as_deref(),
+        service.as_deref(),
+        country.as_deref(),
+    ) {
+        Ok(filter) => filter,
+        Err(message) => {
+            return (
+                Status::BadRequest,
+                Formatted::Json(Json(RangesApiResponse {
+                    status: "error".to_string(),
+                    data: None,
+                    message: Some(message),
+                })),
+            );
+        }
+    };
+
+    // Run every selected provider through the same filter, sorting by prefix
+    let mut records: Vec<Arc<NormalizedPrefix>> = Vec::new();
+    for integration_name in filter.selected_providers() {
+        if let Some(cached) = cache::get(integration_name) {
+            records.extend(
+                cached
+                    .prefixes()
+                    .iter()
+                    .filter(|prefix| filter.matches(prefix))
+                    .cloned(),
+            );
+        }
+    }
+    records.sort_by(|a, b| (a.network, &a.provider).cmp(&(b.network, &b.provider)));
+
+    // If filtered data is found, return it in the requested format
+    if !records.is_empty() {
+        info!(
+            request_id = %request_id,
+            ranges = records.len(),
+            "Ranges found for request"
+        );
+        let filters: Vec<(&str, &str)> = [
+            ("provider", &provider),
+            ("family", &family),
+            ("region", &region),
+            ("service", &service),
+            ("country", &country),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.as_deref().map(|value| (key, value)))
+        .collect();
+        return (
+            Status::Ok,
+            Formatted::render(
+                format,
+                &filters,
+                records.iter().map(|record| record.as_ref()),
+                || {
+                    // The JSON envelope lists each prefix of a provider once
+                    let ranges: BTreeSet<(IpNet, &str)> = records
+                        .iter()
+                        .map(|record| (record.network, record.provider.as_str()))
+                        .collect();
+                    RangesApiResponse {
+                        status: "success".to_string(),
+                        data: Some(
+                            ranges
+                                .into_iter()
+                                .map(|(network, provider)| RangeEntry {
+                                    prefix: network,
+                                    family: Family::of(&network),
+                                    provider: provider.to_string(),
+                                })
+                                .collect(),
+                        ),
+                        message: None,
+                    }
+                },
+            ),
+        );
+    }
+
+    // Log failure to retrieve ranges
+    error!(
+        request_id = %request_id,
+        "Failed to retrieve ranges"
+    );
+
+    (
+        Status::NotFound,
+        Formatted::Json(Json(RangesApiResponse {
+            status: "error".to_string(),
+            data: None,
+            message: Some("Ranges not found".to_string()),
+        })),
+    )
+}
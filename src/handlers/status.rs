@@ -0,0 +1,114 @@
+use crate::cache;
+use crate::fetchers::normalized::Family;
+use crate::fetchers::{registry, UpstreamVersion};
+use chrono::{DateTime, Utc};
+use rocket::get;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use serde::Serialize;
+use tracing::info;
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct StatusApiResponse<T> {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PrefixCounts {
+    pub total: usize,
+    pub ipv4: usize,
+    pub ipv6: usize,
+    pub rejected: usize,
+}
+
+#[derive(Serialize)]
+pub struct ProviderStatus {
+    pub provider: &'static str,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_changed: Option<DateTime<Utc>>,
+    pub last_attempt: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_error_kind: Option<&'static str>,
+    pub version: Option<UpstreamVersion>,
+    pub prefixes: Option<PrefixCounts>,
+    pub data_age_seconds: Option<i64>,
+    // Age of data restored from a snapshot that no fetch has confirmed yet
+    pub snapshot_age_seconds: Option<i64>,
+}
+
+#[get("/v1/status")]
+pub fn query_status_data() -> (Status, Json<StatusApiResponse<Vec<ProviderStatus>>>) {
+    // Generate a unique request ID
+    let request_id = Uuid::new_v4();
+
+    info!(
+        request_id = %request_id,
+        "Received status request"
+    );
+
+    let now = Utc::now();
+    let providers = registry()
+        .iter()
+        .map(|integration| {
+            let cached = cache::get(integration.name());
+            let attempt = cache::last_attempt(integration.name());
+
+            // Count the served prefixes of the cached data, if any fetch has succeeded
+            let prefixes = cached.as_ref().map(|cached| {
+                let ipv4 = cached
+                    .prefixes()
+                    .iter()
+                    .filter(|prefix| prefix.family == Family::Ipv4)
+                    .count();
+                PrefixCounts {
+                    total: cached.prefixes().len(),
+                    ipv4,
+                    ipv6: cached.prefixes().len() - ipv4,
+                    rejected: cached.rejected,
+                }
+            });
+
+            let error = attempt.as_ref().and_then(|attempt| attempt.error.as_ref());
+
+            // Data confirmed unchanged upstream is as fresh as the check that confirmed it
+            let last_success = attempt.as_ref().and_then(|attempt| attempt.last_success);
+
+            // Until then, restored data is as old as the fetch that wrote its snapshot
+            let snapshot_at = cached
+                .as_ref()
+                .filter(|cached| cached.restored && last_success.is_none())
+                .map(|cached| cached.fetched_at);
+
+            ProviderStatus {
+                provider: integration.name(),
+                last_success,
+                last_changed: cached.as_ref().map(|cached| cached.fetched_at),
+                last_attempt: attempt.as_ref().map(|attempt| attempt.at),
+                last_error: error.map(|error| error.to_string()),
+                last_error_kind: error.map(|error| error.kind()),
+                version: cached.as_ref().and_then(|cached| cached.version.clone()),
+                prefixes,
+                data_age_seconds: last_success
+                    .filter(|_| cached.is_some())
+                    .or(snapshot_at)
+                    .map(|last_success| (now - last_success).num_seconds()),
+                snapshot_age_seconds: snapshot_at
+                    .map(|snapshot_at| (now - snapshot_at).num_seconds()),
+            }
+        })
+        .collect();
+
+    (
+        Status::Ok,
+        Json(StatusApiResponse {
+            status: "success".to_string(),
+            data: Some(providers),
+            message: None,
+        }),
+    )
+}
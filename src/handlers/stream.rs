@@ -0,0 +1,194 @@
+use crate::fetchers::normalized::NormalizedPrefix;
+use crate::fetchers::UpstreamVersion;
+use crate::filter::PrefixFilter;
+use crate::stream::{self, StreamEvent};
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::stream::{Event, EventStream};
+use rocket::serde::json::Json;
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{get, Shutdown};
+use serde::Serialize;
+use tracing::info;
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct StreamApiResponse<T> {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+// Id of the last event a reconnecting EventSource received
+pub struct LastEventId(Option<u64>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LastEventId {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(LastEventId(
+            request
+                .headers()
+                .get_one("Last-Event-ID")
+                .and_then(|id| id.trim().parse().ok()),
+        ))
+    }
+}
+
+#[derive(Serialize)]
+struct GenerationEvent<'a> {
+    id: u64,
+    provider: &'a str,
+    fetched_at: DateTime<Utc>,
+    version: Option<&'a UpstreamVersion>,
+    added: usize,
+    removed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deltas: Option<Deltas<'a>>,
+}
+
+#[derive(Serialize)]
+struct Deltas<'a> {
+    added: Vec<&'a NormalizedPrefix>,
+    removed: Vec<&'a NormalizedPrefix>,
+}
+
+// Render a generation for a client, unless its filter excludes every change of it
+fn render(filter: &PrefixFilter, event: &StreamEvent, deltas: bool) -> Option<Event> {
+    if !filter.providers.is_empty() && !filter.providers.contains(&event.provider) {
+        return None;
+    }
+
+    let added: Vec<&NormalizedPrefix> = event
+        .generation
+        .added
+        .iter()
+        .filter(|prefix| filter.matches(prefix))
+        .collect();
+    let removed: Vec<&NormalizedPrefix> = event
+        .generation
+        .removed
+        .iter()
+        .filter(|prefix| filter.matches(prefix))
+        .collect();
+
+    // Generations without changes are still sent to clients that filter by provider only
+    let prefix_filtered = !filter.families.is_empty()
+        || !filter.regions.is_empty()
+        || !filter.services.is_empty()
+        || !filter.countries.is_empty();
+    if prefix_filtered && added.is_empty() && removed.is_empty() {
+        return None;
+    }
+
+    let data = GenerationEvent {
+        id: event.id,
+        provider: &event.provider,
+        fetched_at: event.generation.fetched_at,
+        version: event.generation.version.as_ref(),
+        added: added.len(),
+        removed: removed.len(),
+        deltas: deltas.then_some(Deltas { added, removed }),
+    };
+    Some(
+        Event::json(&data)
+            .event("generation")
+            .id(event.id.to_string()),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+#[get("/v1/stream?<provider>&<family>&<region>&<service>&<country>&<deltas>&<last_event_id>")]
+pub fn stream_changes(
+    provider: Option<String>,
+    family: Option<String>,
+    region: Option<String>,
+    service: Option<String>,
+    country: Option<String>,
+    deltas: Option<bool>,
+    last_event_id: Option<u64>,
+    header_last_event_id: LastEventId,
+    mut shutdown: Shutdown,
+) -> Result<EventStream![], (Status, Json<StreamApiResponse<()>>)> {
+    // Generate a unique request ID
+    let request_id = Uuid::new_v4();
+
+    // The header is set by EventSource reconnects, the parameter lets clients resume explicitly
+    let last_event_id = header_last_event_id.0.or(last_event_id);
+
+    info!(
+        request_id = %request_id,
+        provider = provider.clone().map(|s| s.to_lowercase()),
+        family = family.clone().map(|s| s.to_lowercase()),
+        region = region.clone().map(|s| s.to_lowercase()),
+        service = service.clone().map(|s| s.to_lowercase()),
+        country = country.clone().map(|s| s.to_lowercase()),
+        last_event_id = last_event_id,
+        "Received stream request"
+    );
+
+    let filter = PrefixFilter::parse(
+        provider.as_deref(),
+        family.as_deref(),
+        region.as_deref(),
+        service.as_deref(),
+        country.as_deref(),
+    )
+    .map_err(|message| {
+        (
+            Status::BadRequest,
+            Json(StreamApiResponse {
+                status: "error".to_string(),
+                data: None,
+                message: Some(message),
+            }),
+        )
+    })?;
+    let deltas = deltas.unwrap_or(false);
+
+    let subscription = stream::subscribe(last_event_id);
+    Ok(EventStream! {
+        let mut receiver = subscription.receiver;
+
+        // Tell the client to reload the full data when events it needs are gone
+        if subscription.missed {
+            yield Event::data("").event("resync");
+        }
+
+        let mut last_sent = last_event_id.unwrap_or(0);
+        for event in subscription.replay {
+            last_sent = event.id;
+            if let Some(event) = render(&filter, &event, deltas) {
+                yield event;
+            }
+        }
+
+        loop {
+            let event = select! {
+                event = receiver.recv() => match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(_)) => {
+                        yield Event::data("").event("resync");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = &mut shutdown => break,
+            };
+
+            // Skip events already replayed from the buffer
+            if event.id <= last_sent {
+                continue;
+            }
+            last_sent = event.id;
+            if let Some(event) = render(&filter, &event, deltas) {
+                yield event;
+            }
+        }
+    })
+}
@@ -0,0 +1,36 @@
+use crate::webhooks::{self, Delivery};
+use rocket::get;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use serde::Serialize;
+use tracing::info;
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct WebhooksApiResponse<T> {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[get("/v1/webhooks/deliveries")]
+pub fn query_webhook_deliveries() -> (Status, Json<WebhooksApiResponse<Vec<Delivery>>>) {
+    // Generate a unique request ID
+    let request_id = Uuid::new_v4();
+
+    info!(
+        request_id = %request_id,
+        "Received webhook deliveries request"
+    );
+
+    (
+        Status::Ok,
+        Json(WebhooksApiResponse {
+            status: "success".to_string(),
+            data: Some(webhooks::deliveries()),
+            message: None,
+        }),
+    )
+}
@@ -0,0 +1,193 @@
+use crate::cache::IntegrationCache;
+use crate::config;
+use crate::fetchers::normalized::NormalizedPrefix;
+use crate::fetchers::UpstreamVersion;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+
+// Prefixes added and removed by one accepted update of a provider
+#[derive(Debug, Clone, Serialize)]
+pub struct Generation {
+    pub fetched_at: DateTime<Utc>,
+    pub version: Option<UpstreamVersion>,
+    // Version of the data the generation replaced
+    pub previous_version: Option<UpstreamVersion>,
+    pub added: Vec<NormalizedPrefix>,
+    pub removed: Vec<NormalizedPrefix>,
+}
+
+// Generations of a provider, following the data it started from
+struct ProviderHistory {
+    since: DateTime<Utc>,
+    since_version: Option<UpstreamVersion>,
+    generations: VecDeque<Generation>,
+}
+
+// Generations of a provider after a point in time or sync token
+#[derive(Debug, Serialize)]
+pub struct ProviderChanges {
+    pub provider: String,
+    // Time of the oldest data the history starts from
+    pub tracked_since: DateTime<Utc>,
+    // Whether the history reaches back to the requested point
+    pub complete: bool,
+    pub generations: Vec<Generation>,
+}
+
+pub enum Since {
+    Time(DateTime<Utc>),
+    Token(String),
+}
+
+impl Since {
+    // RFC 3339 timestamps select by time, anything else is taken as an upstream sync token
+    pub fn parse(value: &str) -> Self {
+        match DateTime::parse_from_rfc3339(value) {
+            Ok(time) => Since::Time(time.with_timezone(&Utc)),
+            Err(_) => Since::Token(value.to_string()),
+        }
+    }
+}
+
+lazy_static! {
+    // Change history of every integration, bounded by the configured history_limit
+    static ref HISTORY: DashMap<String, ProviderHistory> = DashMap::new();
+}
+
+// Record the difference between the served data of an integration and the update replacing it,
+// returning it unless the update is the first data of the integration or changes nothing
+pub fn record(
+    integration_name: &str,
+    previous: Option<&IntegrationCache>,
+    current: &IntegrationCache,
+) -> Option<Generation> {
+    let limit = config::get().history_limit;
+
+    // The first data of an integration is the baseline later generations are compared with
+    let previous = match previous {
+        Some(previous) => previous,
+        None => {
+            if limit > 0 {
+                HISTORY
+                    .entry(integration_name.to_string())
+                    .or_insert_with(|| ProviderHistory {
+                        since: current.fetched_at,
+                        since_version: current.version.clone(),
+                        generations: VecDeque::new(),
+                    });
+            }
+            return None;
+        }
+    };
+
+    let previous_prefixes: HashSet<&NormalizedPrefix> = previous
+        .prefixes()
+        .iter()
+        .map(|prefix| prefix.as_ref())
+        .collect();
+    let current_prefixes: HashSet<&NormalizedPrefix> = current
+        .prefixes()
+        .iter()
+        .map(|prefix| prefix.as_ref())
+        .collect();
+
+    let generation = Generation {
+        fetched_at: current.fetched_at,
+        version: current.version.clone(),
+        previous_version: previous.version.clone(),
+        added: sorted(current_prefixes.difference(&previous_prefixes)),
+        removed: sorted(previous_prefixes.difference(&current_prefixes)),
+    };
+
+    // Refetches of identical data, from upstreams without validators or after validators were
+    // cleared, would otherwise push real changes out of the history. A new upstream version is
+    // kept even without changes, so its sync token can still be used with since.
+    if generation.added.is_empty()
+        && generation.removed.is_empty()
+        && generation.version == previous.version
+    {
+        return None;
+    }
+
+    if limit > 0 {
+        let mut history = HISTORY
+            .entry(integration_name.to_string())
+            .or_insert_with(|| ProviderHistory {
+                since: previous.fetched_at,
+                since_version: previous.version.clone(),
+                generations: VecDeque::new(),
+            });
+        history.generations.push_back(generation.clone());
+
+        // Forget the oldest generations, whose data becomes the new baseline
+        while history.generations.len() > limit {
+            if let Some(oldest) = history.generations.pop_front() {
+                history.since = oldest.fetched_at;
+                history.since_version = oldest.version;
+            }
+        }
+    }
+
+    Some(generation)
+}
+
+// Generations of an integration after the given time or sync token, all of them without one
+pub fn changes(integration_name: &str, since: Option<&Since>) -> Result<ProviderChanges, String> {
+    let history = match HISTORY.get(integration_name) {
+        Some(history) => history,
+        None => return Err(format!("No history recorded for {} yet", integration_name)),
+    };
+
+    let token_of =
+        |version: Option<&UpstreamVersion>| version.and_then(|version| version.sync_token.clone());
+
+    let (complete, generations): (bool, Vec<Generation>) = match since {
+        None => (true, history.generations.iter().cloned().collect()),
+        Some(Since::Time(time)) => (
+            *time >= history.since,
+            history
+                .generations
+                .iter()
+                .filter(|generation| generation.fetched_at > *time)
+                .cloned()
+                .collect(),
+        ),
+        Some(Since::Token(token)) => {
+            let start = if token_of(history.since_version.as_ref()).as_ref() == Some(token) {
+                0
+            } else {
+                match history.generations.iter().position(|generation| {
+                    token_of(generation.version.as_ref()).as_ref() == Some(token)
+                }) {
+                    Some(position) => position + 1,
+                    None => {
+                        return Err(format!(
+                            "Sync token {} not found in {} history",
+                            token, integration_name
+                        ))
+                    }
+                }
+            };
+            (
+                true,
+                history.generations.iter().skip(start).cloned().collect(),
+            )
+        }
+    };
+
+    Ok(ProviderChanges {
+        provider: integration_name.to_string(),
+        tracked_since: history.since,
+        complete,
+        generations,
+    })
+}
+
+fn sorted<'a>(prefixes: impl Iterator<Item = &'a &'a NormalizedPrefix>) -> Vec<NormalizedPrefix> {
+    let mut prefixes: Vec<NormalizedPrefix> = prefixes.map(|prefix| (*prefix).clone()).collect();
+    prefixes.sort_by_key(|prefix| prefix.network);
+    prefixes
+}
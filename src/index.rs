@@ -0,0 +1,173 @@
+use crate::cache::{IntegrationCache, CACHE};
+use crate::fetchers::normalized::NormalizedPrefix;
+use ipnet::IpNet;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+#[derive(Default)]
+struct TrieNode {
+    children: [Option<usize>; 2],
+    entries: Vec<usize>,
+}
+
+// Binary radix trie keyed on the network bits, left-aligned in a u128
+struct PrefixTrie {
+    nodes: Vec<TrieNode>,
+}
+
+impl Default for PrefixTrie {
+    fn default() -> Self {
+        PrefixTrie {
+            nodes: vec![TrieNode::default()],
+        }
+    }
+}
+
+impl PrefixTrie {
+    fn insert(&mut self, key: u128, prefix_len: u8, entry: usize) {
+        let mut node = 0;
+        for depth in 0..prefix_len {
+            let bit = bit_at(key, depth);
+            node = match self.nodes[node].children[bit] {
+                Some(child) => child,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children[bit] = Some(child);
+                    child
+                }
+            };
+        }
+        self.nodes[node].entries.push(entry);
+    }
+
+    // Walk the path of the key, yielding the entries of every prefix that covers it, shortest first
+    fn covering(&self, key: u128, prefix_len: u8) -> Vec<&[usize]> {
+        let mut found = Vec::new();
+        let mut node = 0;
+        for depth in 0..=prefix_len {
+            if !self.nodes[node].entries.is_empty() {
+                found.push(self.nodes[node].entries.as_slice());
+            }
+            if depth == prefix_len {
+                break;
+            }
+            match self.nodes[node].children[bit_at(key, depth)] {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+        found
+    }
+}
+
+#[derive(Default)]
+pub struct PrefixIndex {
+    records: Vec<Arc<NormalizedPrefix>>,
+    ipv4: PrefixTrie,
+    ipv6: PrefixTrie,
+}
+
+impl PrefixIndex {
+    pub fn build(records: impl IntoIterator<Item = Arc<NormalizedPrefix>>) -> Self {
+        let mut index = PrefixIndex::default();
+        for record in records {
+            let entry = index.records.len();
+            let (key, prefix_len) = trie_key(&record.network);
+            match record.network {
+                IpNet::V4(_) => index.ipv4.insert(key, prefix_len, entry),
+                IpNet::V6(_) => index.ipv6.insert(key, prefix_len, entry),
+            }
+            index.records.push(record);
+        }
+        index
+    }
+
+    pub fn records(&self) -> &[Arc<NormalizedPrefix>] {
+        &self.records
+    }
+
+    // Every indexed prefix that contains the given network, ordered from shortest to longest
+    pub fn covering(&self, network: &IpNet) -> Vec<Arc<NormalizedPrefix>> {
+        self.matching_levels(network)
+            .into_iter()
+            .flatten()
+            .map(|&entry| self.records[entry].clone())
+            .collect()
+    }
+
+    // Only the most specific indexed prefixes that contain the given network
+    pub fn longest_match(&self, network: &IpNet) -> Vec<Arc<NormalizedPrefix>> {
+        self.matching_levels(network)
+            .last()
+            .map_or_else(Vec::new, |entries| {
+                entries
+                    .iter()
+                    .map(|&entry| self.records[entry].clone())
+                    .collect()
+            })
+    }
+
+    fn matching_levels(&self, network: &IpNet) -> Vec<&[usize]> {
+        let (key, prefix_len) = trie_key(network);
+        match network {
+            IpNet::V4(_) => self.ipv4.covering(key, prefix_len),
+            IpNet::V6(_) => self.ipv6.covering(key, prefix_len),
+        }
+    }
+}
+
+// Indexes of every cached provider, in name order. Searching them in turn avoids rebuilding a
+// merged index whenever one provider refreshes
+fn provider_indexes() -> Vec<Arc<IntegrationCache>> {
+    let mut cached: Vec<(String, Arc<IntegrationCache>)> = CACHE
+        .iter()
+        .map(|entry| (entry.key().clone(), entry.value().clone()))
+        .collect();
+    cached.sort_by(|(a, _), (b, _)| a.cmp(b));
+    cached.into_iter().map(|(_, cached)| cached).collect()
+}
+
+// Every prefix of any provider that contains the given network, ordered from shortest to longest
+pub fn covering_all(network: &IpNet) -> Vec<Arc<NormalizedPrefix>> {
+    let mut found: Vec<Arc<NormalizedPrefix>> = provider_indexes()
+        .iter()
+        .flat_map(|cached| cached.index.covering(network))
+        .collect();
+    found.sort_by_key(|record| record.network.prefix_len());
+    found
+}
+
+// Only the most specific prefixes of any provider that contain the given network
+pub fn longest_match_all(network: &IpNet) -> Vec<Arc<NormalizedPrefix>> {
+    let found: Vec<Arc<NormalizedPrefix>> = provider_indexes()
+        .iter()
+        .flat_map(|cached| cached.index.longest_match(network))
+        .collect();
+    let longest = found.iter().map(|record| record.network.prefix_len()).max();
+    found
+        .into_iter()
+        .filter(|record| Some(record.network.prefix_len()) == longest)
+        .collect()
+}
+
+// Turn a lookup value into a network, treating a bare address as a host prefix
+pub fn parse_network(value: &str) -> Option<IpNet> {
+    let value = value.trim();
+    value
+        .parse::<IpNet>()
+        .map(|network| network.trunc())
+        .ok()
+        .or_else(|| value.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+fn trie_key(network: &IpNet) -> (u128, u8) {
+    match network {
+        IpNet::V4(net) => ((u32::from(net.network()) as u128) << 96, net.prefix_len()),
+        IpNet::V6(net) => (u128::from(net.network()), net.prefix_len()),
+    }
+}
+
+fn bit_at(key: u128, depth: u8) -> usize {
+    ((key >> (127 - depth)) & 1) as usize
+}
@@ -1,13 +1,24 @@
 mod cache;
+mod config;
+mod export;
 mod fetchers;
+mod filter;
 mod handlers;
-
-use cache::initialize_cache;
+mod history;
+mod index;
+mod snapshot;
+mod stream;
+mod webhooks;
+
+use cache::{initialize_cache, restore_snapshots};
+use fetchers::http::HttpClient;
 use rocket::Config;
-use std::env;
-use tracing::{info, Level};
+use std::sync::Arc;
+use tokio::task;
+use tracing::Level;
 
 #[rocket::main]
+#[allow(clippy::result_large_err)]
 async fn main() -> Result<(), rocket::Error> {
     // Initialize tracing subscriber with appropriate settings
     let subscriber = tracing_subscriber::fmt()
@@ -16,17 +27,29 @@ async fn main() -> Result<(), rocket::Error> {
 
     tracing::subscriber::set_global_default(subscriber).expect("Failed to set subscriber");
 
-    // Initialize the cache and start periodic updates
-    initialize_cache().await;
-    info!("Cache initialized");
+    // Load the config file and environment overrides before anything reads them
+    let settings = config::get();
+
+    // Build the HTTP client shared by every integration
+    let client = Arc::new(
+        HttpClient::from_config(&settings.http)
+            .unwrap_or_else(|err| panic!("Invalid HTTP configuration: {}", err)),
+    );
+
+    // Validate the webhook targets and start delivering to them before any update is sent
+    webhooks::start(&client);
+
+    // Serve the data of the previous run while the first fetches are in flight
+    restore_snapshots();
 
-    // Get the port from the environment variable, default to 8000 if not set
-    let port = env::var("PORT").unwrap_or_else(|_| "8000".to_string());
+    // Fill the cache and start periodic updates in the background, so the server binds
+    // immediately and reports readiness through /health/ready
+    task::spawn(initialize_cache(client));
 
-    // Configure Rocket to bind to 0.0.0.0:port
+    // Configure Rocket to bind to the configured address and port
     let config = Config {
-        address: "0.0.0.0".parse().unwrap(),
-        port: port.parse().unwrap(),
+        address: settings.address,
+        port: settings.port,
         ..Config::default()
     };
 
@@ -0,0 +1,69 @@
+use crate::cache::IntegrationCache;
+use crate::config;
+use crate::fetchers::normalized::NormalizedPrefix;
+use crate::fetchers::UpstreamVersion;
+use chrono::{DateTime, Utc};
+use rocket::serde::json::serde_json;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+// On-disk form of an integration's cached data
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    provider: String,
+    fetched_at: DateTime<Utc>,
+    rejected: usize,
+    version: Option<UpstreamVersion>,
+    prefixes: Vec<NormalizedPrefix>,
+}
+
+fn path(integration_name: &str) -> PathBuf {
+    config::get()
+        .snapshot
+        .dir
+        .join(format!("{}.json", integration_name))
+}
+
+// Write the data of an integration, replacing its previous snapshot atomically
+pub fn save(integration_name: &str, integration_cache: &IntegrationCache) -> Result<(), String> {
+    let snapshot = Snapshot {
+        provider: integration_name.to_string(),
+        fetched_at: integration_cache.fetched_at,
+        rejected: integration_cache.rejected,
+        version: integration_cache.version.clone(),
+        prefixes: integration_cache
+            .prefixes()
+            .iter()
+            .map(|prefix| prefix.as_ref().clone())
+            .collect(),
+    };
+    let data = serde_json::to_vec(&snapshot)
+        .map_err(|err| format!("Failed to serialize snapshot: {}", err))?;
+
+    let path = path(integration_name);
+    let temporary = path.with_extension("json.tmp");
+    fs::create_dir_all(&config::get().snapshot.dir)
+        .and_then(|_| fs::write(&temporary, data))
+        .and_then(|_| fs::rename(&temporary, &path))
+        .map_err(|err| format!("Failed to write {}: {}", path.display(), err))
+}
+
+// Read the snapshot of an integration, if one was written
+pub fn load(integration_name: &str) -> Result<Option<IntegrationCache>, String> {
+    let path = path(integration_name);
+    let data = match fs::read(&path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("Failed to read {}: {}", path.display(), err)),
+    };
+
+    let snapshot: Snapshot = serde_json::from_slice(&data)
+        .map_err(|err| format!("Invalid snapshot {}: {}", path.display(), err))?;
+    Ok(Some(IntegrationCache::restore(
+        snapshot.prefixes,
+        snapshot.rejected,
+        snapshot.version,
+        snapshot.fetched_at,
+    )))
+}
@@ -0,0 +1,89 @@
+use crate::history::Generation;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+// Events kept for clients resuming with a Last-Event-ID, and queued for slow clients
+const EVENT_BUFFER_SIZE: usize = 1000;
+
+// A generation swap of a provider, numbered in the order it was served
+#[derive(Debug)]
+pub struct StreamEvent {
+    pub id: u64,
+    pub provider: String,
+    pub generation: Generation,
+}
+
+struct EventLog {
+    last_id: u64,
+    events: VecDeque<Arc<StreamEvent>>,
+}
+
+lazy_static! {
+    // Ids start from the startup time, so ids seen before a restart are recognized as missed
+    static ref EVENTS: Mutex<EventLog> = Mutex::new(EventLog {
+        last_id: Utc::now().timestamp_micros() as u64,
+        events: VecDeque::new(),
+    });
+    static ref SENDER: broadcast::Sender<Arc<StreamEvent>> =
+        broadcast::channel(EVENT_BUFFER_SIZE).0;
+}
+
+// Number a generation and send it to every connected client, returning its id
+pub fn publish(integration_name: &str, generation: &Generation) -> u64 {
+    let mut log = EVENTS.lock().unwrap_or_else(|err| err.into_inner());
+    log.last_id += 1;
+    let event = Arc::new(StreamEvent {
+        id: log.last_id,
+        provider: integration_name.to_string(),
+        generation: generation.clone(),
+    });
+
+    log.events.push_back(event.clone());
+    while log.events.len() > EVENT_BUFFER_SIZE {
+        log.events.pop_front();
+    }
+
+    // Sending only fails without subscribers
+    let _ = SENDER.send(event);
+    log.last_id
+}
+
+// Events following a subscriber's last seen event
+pub struct Subscription {
+    pub replay: Vec<Arc<StreamEvent>>,
+    // The last seen event is no longer buffered or predates a restart, so events were missed
+    pub missed: bool,
+    pub receiver: broadcast::Receiver<Arc<StreamEvent>>,
+}
+
+pub fn subscribe(last_event_id: Option<u64>) -> Subscription {
+    // Subscribe while holding the log, so no event falls between the replay and the live events
+    let log = EVENTS.lock().unwrap_or_else(|err| err.into_inner());
+    let receiver = SENDER.subscribe();
+
+    let last_event_id = match last_event_id {
+        Some(last_event_id) => last_event_id,
+        None => {
+            return Subscription {
+                replay: Vec::new(),
+                missed: false,
+                receiver,
+            }
+        }
+    };
+
+    let oldest_id = log.events.front().map_or(log.last_id + 1, |event| event.id);
+    Subscription {
+        replay: log
+            .events
+            .iter()
+            .filter(|event| event.id > last_event_id)
+            .cloned()
+            .collect(),
+        missed: last_event_id > log.last_id || last_event_id + 1 < oldest_id,
+        receiver,
+    }
+}